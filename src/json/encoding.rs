@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+use super::{JsonError, JsonErrorKind, Position, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// the encoding of `bytes` and the length of its byte order mark.
+/// without a BOM the encoding is told by where the first two characters,
+/// which JSON text starts with as ASCII, have zero bytes (RFC 4627 section 3)
+fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0, 0, 0xfe, 0xff, ..] => (Encoding::Utf32Be, 4),
+        [0xff, 0xfe, 0, 0, ..] => (Encoding::Utf32Le, 4),
+        [0xfe, 0xff, ..] => (Encoding::Utf16Be, 2),
+        [0xff, 0xfe, ..] => (Encoding::Utf16Le, 2),
+        [0xef, 0xbb, 0xbf, ..] => (Encoding::Utf8, 3),
+        [0, 0, 0, _, ..] => (Encoding::Utf32Be, 0),
+        [_, 0, 0, 0, ..] => (Encoding::Utf32Le, 0),
+        [0, _, ..] => (Encoding::Utf16Be, 0),
+        [_, 0, ..] => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+/// the JSON text in `bytes` as UTF-8 without its BOM, together with the
+/// number of bytes before it in `bytes` when it was not transcoded
+pub(crate) fn decode(bytes: &[u8]) -> Result<(Cow<'_, str>, usize)> {
+    let (encoding, bom) = detect(bytes);
+    let body = &bytes[bom..];
+    let text = match encoding {
+        Encoding::Utf8 => {
+            return match std::str::from_utf8(body) {
+                Ok(text) => Ok((Cow::Borrowed(text), bom)),
+                Err(err) => {
+                    let valid = std::str::from_utf8(&body[..err.valid_up_to()]).unwrap();
+                    Err(invalid("Invalid UTF-8", valid, bom + err.valid_up_to()))
+                }
+            };
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let even = body.len() - body.len() % 2;
+            let units = body[..even].chunks_exact(2).map(|c| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([c[0], c[1]]),
+                _ => u16::from_be_bytes([c[0], c[1]]),
+            });
+            let mut text = String::with_capacity(even / 2);
+            let mut offset = bom;
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok(c) => {
+                        text.push(c);
+                        offset += c.len_utf16() * 2;
+                    }
+                    Err(_) => return Err(invalid("Invalid UTF-16", &text, offset)),
+                }
+            }
+            if even != body.len() {
+                return Err(invalid("Truncated UTF-16", &text, offset));
+            }
+            text
+        }
+        Encoding::Utf32Le | Encoding::Utf32Be => {
+            let mut text = String::with_capacity(body.len() / 4);
+            for (i, c) in body.chunks(4).enumerate() {
+                let offset = bom + i * 4;
+                let code = match c.try_into() {
+                    Ok(c) if encoding == Encoding::Utf32Le => u32::from_le_bytes(c),
+                    Ok(c) => u32::from_be_bytes(c),
+                    Err(_) => return Err(invalid("Truncated UTF-32", &text, offset)),
+                };
+                match char::from_u32(code) {
+                    Some(c) => text.push(c),
+                    None => return Err(invalid("Invalid UTF-32", &text, offset)),
+                }
+            }
+            text
+        }
+    };
+    Ok((Cow::Owned(text), 0))
+}
+
+/// an encoding error at byte `offset`, `before` is the text decoded up to it
+fn invalid(message: &str, before: &str, offset: usize) -> JsonError {
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    JsonError::new(JsonErrorKind::Syntax, message).at(Position::new(offset, line, column))
+}
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, mem};
+
+use super::{
+    json_parser::put_member, json_pull_parser::Grammar, JsonArray, JsonElement, JsonError,
+    JsonErrorKind, JsonEvent, JsonObject, JsonPrimitive, JsonType, Lexer, ParseOptions, Position,
+    Result, Source, Span, Token, TokenType,
+};
+
+/// array or object whose closing bracket has not been fed yet, `start` is
+/// the position of its opening bracket
+enum Frame {
+    Array {
+        array: Box<JsonArray>,
+        start: Position,
+    },
+    Object {
+        object: Box<JsonObject>,
+        start: Position,
+        /// key of the member whose value comes next
+        key: Option<(String, Span)>,
+        /// first position of each key, kept for the policies that need it
+        seen: HashMap<String, Position>,
+    },
+}
+
+/// a string, comment or other token cut off at the start of `pending`.
+/// the chunks after it are scanned for its end before it is lexed again,
+/// so a long token costs one scan of its bytes, not one per chunk
+struct Cut {
+    kind: CutKind,
+    /// bytes of `pending` scanned so far
+    scanned: usize,
+    /// a string this long is lexed again before it ends, to notice it
+    /// passing `ParseLimits::max_string_length`. doubled every time
+    check_at: usize,
+}
+
+enum CutKind {
+    /// `escape` when the last byte scanned starts an escape
+    String {
+        quote: u8,
+        escape: bool,
+    },
+    LineComment,
+    /// `star` when the last byte scanned is a `*`
+    BlockComment {
+        star: bool,
+    },
+    /// a number, keyword or identifier, or the start of anything else
+    Word,
+}
+
+impl Cut {
+    fn new(bytes: &[u8], json5: bool, check_at: usize) -> Cut {
+        let (kind, scanned) = match bytes {
+            [quote @ (b'"' | b'\''), ..] if *quote == b'"' || json5 => {
+                let (quote, escape) = (*quote, false);
+                (CutKind::String { quote, escape }, 1)
+            }
+            [b'/', b'/', ..] => (CutKind::LineComment, 2),
+            [b'/', b'*', ..] => (CutKind::BlockComment { star: false }, 2),
+            _ => (CutKind::Word, 1),
+        };
+        Cut {
+            kind,
+            scanned,
+            check_at,
+        }
+    }
+
+    /// scan the bytes of `pending` not scanned yet, true once the token
+    /// may have ended and is worth lexing
+    fn scan(&mut self, pending: &[u8]) -> bool {
+        let from = self.scanned;
+        self.scanned = pending.len();
+        for &c in &pending[from..] {
+            let end = match &mut self.kind {
+                CutKind::String { quote, escape } => {
+                    let end = !*escape && c == *quote;
+                    *escape = !*escape && c == b'\\';
+                    end
+                }
+                CutKind::LineComment => c == b'\n',
+                CutKind::BlockComment { star } => {
+                    let end = *star && c == b'/';
+                    *star = c == b'*';
+                    end
+                }
+                CutKind::Word => {
+                    !(c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.' | b'_' | b'$'))
+                }
+            };
+            if end {
+                return true;
+            }
+        }
+        matches!(self.kind, CutKind::String { .. }) && pending.len() >= self.check_at
+    }
+}
+
+///parses JSON handed over in chunks of any size, such as the body of an
+///HTTP response as it arrives, without buffering the whole input
+///
+///feed() every chunk and finish() at the end of input, both return the
+///values completed by then. values written back to back are returned one
+///after another. a token cut off at the end of a chunk, such as half a
+///string, number or `\u` escape, is kept and lexed once a later chunk ends it
+///
+///the first error ends the parse, it is returned again by later calls
+pub struct IncrementalParser {
+    options: ParseOptions,
+    /// bytes not lexed yet, the start of a token cut off by a chunk end
+    pending: Vec<u8>,
+    /// how far the token at the start of `pending` has been scanned
+    cut: Option<Cut>,
+    /// position of `pending[0]` in the whole input
+    base: Position,
+    grammar: Grammar,
+    stack: Vec<Frame>,
+    error: Option<JsonError>,
+}
+
+impl IncrementalParser {
+    /// a parser for JSON fed in chunks
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{IncrementalParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let mut parser = IncrementalParser::new();
+    ///     assert!(parser.feed(b"{\"player\":\"Asu")?.is_empty());
+    ///     let values = parser.feed(b"na\",\"floor\":74}")?;
+    ///     assert_eq!(values[0]["player"].string()?, "Asuna");
+    ///     assert!(parser.finish()?.is_empty());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new() -> IncrementalParser {
+        IncrementalParser::with_options(&ParseOptions::default())
+    }
+
+    /// a parser for JSON fed in chunks with non-default options
+    pub fn with_options(options: &ParseOptions) -> IncrementalParser {
+        IncrementalParser {
+            options: options.clone(),
+            pending: vec![],
+            cut: None,
+            base: Position::default(),
+            grammar: Grammar::new(options.clone(), true),
+            stack: vec![],
+            error: None,
+        }
+    }
+
+    /// read the input as exactly one value, as `JsonParser::parse` does.
+    /// anything after it is an error, and so is input without a value
+    #[cfg(feature = "tokio")]
+    pub(crate) fn single_value(mut self) -> Self {
+        self.grammar = Grammar::new(self.options.clone(), false);
+        self
+    }
+
+    /// position of the end of the input fed so far
+    pub(crate) fn position(&self) -> Position {
+        advance(self.base, &self.pending)
+    }
+
+    /// parse the next chunk of input, returns the values it completes.
+    /// an object, array or string is returned once its last byte is fed,
+    /// a number or keyword needs the byte after it or `finish()`
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Box<dyn JsonElement>>> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        let result = self.feed_chunk(chunk);
+        if let Err(err) = &result {
+            self.error = Some(err.clone());
+        }
+        result
+    }
+
+    /// end the input, returns the values completed by the last token.
+    /// input ending inside a value is an error
+    pub fn finish(mut self) -> Result<Vec<Box<dyn JsonElement>>> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let values = self.parse_pending(true)?;
+        if !self.grammar.is_complete() {
+            let err = JsonError::new(JsonErrorKind::UnexpectedEof, "Unexpected end of JSON");
+            return Err(err.at(self.position()));
+        }
+        Ok(values)
+    }
+
+    fn feed_chunk(&mut self, chunk: &[u8]) -> Result<Vec<Box<dyn JsonElement>>> {
+        let max = self.options.limits.max_input_bytes;
+        let room = max - (self.base.offset + self.pending.len());
+        self.pending
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+        let values = self.parse_pending(false)?;
+        if chunk.len() > room {
+            let message = format!("Input is larger than {} bytes", max);
+            let err = JsonError::new(JsonErrorKind::InputTooLarge, message);
+            return Err(err.at(self.position()));
+        }
+        Ok(values)
+    }
+
+    /// lex and parse the tokens in `pending`, leaving the one cut off at
+    /// its end. at the `last` chunk nothing is cut off
+    fn parse_pending(&mut self, last: bool) -> Result<Vec<Box<dyn JsonElement>>> {
+        if let Some(cut) = &mut self.cut {
+            if !last && !cut.scan(&self.pending) {
+                return Ok(vec![]);
+            }
+        }
+        let cut = self.cut.take();
+        let pending = mem::take(&mut self.pending);
+        let parsed = self.parse_tokens(&pending, last);
+        self.pending = pending;
+        let (values, start) = parsed?;
+        self.base = advance(self.base, &self.pending[..start]);
+        self.pending.drain(..start);
+        if !last && !self.pending.is_empty() {
+            let check_at = match cut {
+                Some(cut) if start == 0 => cut.check_at.saturating_mul(2),
+                _ => self.options.limits.max_string_length.saturating_add(2),
+            };
+            let mut cut = Cut::new(&self.pending, self.options.json5, check_at);
+            cut.scan(&self.pending);
+            self.cut = Some(cut);
+        }
+        Ok(values)
+    }
+
+    /// parse the tokens of `input` with one lexer, returns the values they
+    /// complete and the length of `input` that was used up
+    fn parse_tokens(
+        &mut self,
+        input: &[u8],
+        last: bool,
+    ) -> Result<(Vec<Box<dyn JsonElement>>, usize)> {
+        let mut values = vec![];
+        let mut lexer = Lexer::new(Source::slice(input));
+        lexer.set_options(&self.options);
+        loop {
+            let token = lexer.next_token();
+            let error = lexer.diagnostic().first().cloned();
+            let end = lexer.offset();
+            let closed = error.is_none()
+                && !matches!(
+                    token.token_type,
+                    TokenType::Eof
+                        | TokenType::Int
+                        | TokenType::Float
+                        | TokenType::NonFinite
+                        | TokenType::Bool
+                        | TokenType::Null
+                        | TokenType::Identifier
+                );
+            // a string past `ParseLimits::max_string_length` is not kept
+            // until it ends
+            let too_long = error
+                .as_ref()
+                .is_some_and(|e| e.kind() == JsonErrorKind::StringTooLong);
+            if !last && end == input.len() && !closed && !too_long {
+                // whitespace and finished comments are dropped, but a line
+                // comment may go on in the next chunk
+                let piece = lexer.piece_start();
+                let keep = match token.token_type {
+                    TokenType::Eof if error.is_none() && !input[piece..].starts_with(b"//") => end,
+                    _ => piece,
+                };
+                return Ok((values, keep));
+            }
+            if let Some(err) = error {
+                return Err(match err.position() {
+                    Some(p) => err.at(shift(self.base, p)),
+                    None => err,
+                });
+            }
+            if token.token_type == TokenType::Eof {
+                return Ok((values, end));
+            }
+            let token = Token {
+                position: shift(self.base, token.position),
+                end: shift(self.base, token.end),
+                ..token
+            };
+            if let Some(value) = self.push_token(token)? {
+                values.push(value);
+            }
+        }
+    }
+
+    /// move the parse on by one token, returns a value completed by it
+    fn push_token(&mut self, token: Token) -> Result<Option<Box<dyn JsonElement>>> {
+        let span = Span::new(token.position, token.end);
+        let value = match self.grammar.push(token, false)? {
+            None => return Ok(None),
+            Some(JsonEvent::StartArray) => {
+                let array = JsonArray::new();
+                self.stack.push(Frame::Array {
+                    array,
+                    start: span.start,
+                });
+                return Ok(None);
+            }
+            Some(JsonEvent::StartObject) => {
+                self.stack.push(Frame::Object {
+                    object: JsonObject::new(),
+                    start: span.start,
+                    key: None,
+                    seen: HashMap::new(),
+                });
+                return Ok(None);
+            }
+            Some(JsonEvent::Key(text)) => {
+                if let Some(Frame::Object { key, .. }) = self.stack.last_mut() {
+                    *key = Some((text.into_owned(), span));
+                }
+                return Ok(None);
+            }
+            Some(JsonEvent::EndArray | JsonEvent::EndObject) => {
+                return self.end_container(span.end)
+            }
+            Some(JsonEvent::String(v)) => JsonType::String(v.into_owned()),
+            Some(JsonEvent::Int(v)) => JsonType::Int(v),
+            Some(JsonEvent::UInt(v)) => JsonType::UInt(v),
+            Some(JsonEvent::Float(v)) => JsonType::Float(v),
+            Some(JsonEvent::RawNumber(v)) => JsonType::RawNumber(v),
+            Some(JsonEvent::Bool(v)) => JsonType::Bool(v),
+            Some(JsonEvent::Null) => JsonType::Null,
+        };
+        let mut primitive = JsonPrimitive::new(value);
+        if self.options.spans {
+            primitive.set_span(span);
+        }
+        self.add_value(primitive)
+    }
+
+    /// the array or object closed at `end`, the grammar checked the
+    /// brackets match
+    fn end_container(&mut self, end: Position) -> Result<Option<Box<dyn JsonElement>>> {
+        let spans = self.options.spans;
+        let ele: Box<dyn JsonElement> = match self.stack.pop() {
+            Some(Frame::Array { mut array, start }) => {
+                if spans {
+                    array.set_span(Span::new(start, end));
+                }
+                array
+            }
+            Some(Frame::Object {
+                mut object, start, ..
+            }) => {
+                if spans {
+                    object.set_span(Span::new(start, end));
+                }
+                object
+            }
+            None => return Ok(None),
+        };
+        self.add_value(ele)
+    }
+
+    /// put a finished value into the array or object it is in, or return
+    /// it at the top level
+    fn add_value(&mut self, ele: Box<dyn JsonElement>) -> Result<Option<Box<dyn JsonElement>>> {
+        match self.stack.last_mut() {
+            None => return Ok(Some(ele)),
+            Some(Frame::Array { array, .. }) => array.push_ele(ele),
+            Some(Frame::Object {
+                object, key, seen, ..
+            }) => {
+                let (key, span) = key.take().unwrap();
+                put_member(&self.options, object, seen, key, span, ele)?;
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for IncrementalParser {
+    fn default() -> Self {
+        IncrementalParser::new()
+    }
+}
+
+/// `p`, lexed from input starting at `base`, as a position in the whole input
+pub(crate) fn shift(base: Position, p: Position) -> Position {
+    let column = if p.line == 1 {
+        base.column + p.column - 1
+    } else {
+        p.column
+    };
+    Position::new(base.offset + p.offset, base.line + p.line - 1, column)
+}
+
+/// the position after `bytes`, which start at `base`. continuation bytes
+/// of a multi-byte character share its column as in the lexer
+pub(crate) fn advance(base: Position, bytes: &[u8]) -> Position {
+    let mut p = base;
+    for &c in bytes {
+        match c {
+            b'\n' => {
+                p.line += 1;
+                p.column = 1;
+            }
+            c if c & 0xC0 == 0x80 => {}
+            _ => p.column += 1,
+        }
+    }
+    p.offset += bytes.len();
+    p
+}
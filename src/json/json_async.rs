@@ -0,0 +1,97 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    json_lines::write_error, json_serializer::write_element, IncrementalParser, JsonElement,
+    JsonError, JsonErrorKind, JsonParser, ParseOptions, Result, SerializeOptions,
+};
+
+/// bytes read from an async reader at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+
+impl JsonParser<'_> {
+    /// parse JSON read from an async `reader`. every chunk is parsed as
+    /// soon as it is read, the task only waits for the reader
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,Result};
+    /// use tokio::io::AsyncWriteExt;
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()>{
+    ///     let (mut client, server) = tokio::io::duplex(16);
+    ///     let send = async move {
+    ///         client.write_all(b"{\"player\":\"Asuna\",\"floor\":74}").await
+    ///     };
+    ///     let (sent, result) = tokio::join!(send, JsonParser::from_async_reader(server));
+    ///     sent.unwrap();
+    ///     assert_eq!(result?["player"].string()?, "Asuna");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<Box<dyn JsonElement>> {
+        JsonParser::from_async_reader_with(reader, &ParseOptions::default()).await
+    }
+
+    /// parse JSON read from an async `reader` with non-default options
+    pub async fn from_async_reader_with<R: AsyncRead + Unpin>(
+        mut reader: R,
+        options: &ParseOptions,
+    ) -> Result<Box<dyn JsonElement>> {
+        let mut parser = IncrementalParser::with_options(options).single_value();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut values = vec![];
+        loop {
+            let read = match reader.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) => {
+                    let message = format!("Failed to read JSON: {}", err);
+                    let err = JsonError::new(JsonErrorKind::Io, message).with_source(err);
+                    return Err(err.at(parser.position()));
+                }
+            };
+            values.append(&mut parser.feed(&buffer[..read])?);
+        }
+        values.append(&mut parser.finish()?);
+        // a single value parser finishes with exactly one value
+        Ok(values.pop().unwrap())
+    }
+}
+
+/// serialize `ele` to an async `writer` and flush it
+///
+/// # Examples
+/// ```
+/// use rjson::{to_async_writer,JsonParser,Result};
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() -> Result<()>{
+///     let ele = JsonParser::parse("[\"Link Start!\"]")?;
+///     let mut out = Vec::new();
+///     to_async_writer(&mut out, &*ele).await?;
+///     assert_eq!(out, b"[\"Link Start!\"]");
+///     Ok(())
+/// }
+/// ```
+pub async fn to_async_writer<W: AsyncWrite + Unpin>(
+    writer: W,
+    ele: &dyn JsonElement,
+) -> Result<()> {
+    to_async_writer_with(writer, ele, &SerializeOptions::default()).await
+}
+
+/// serialize `ele` to an async `writer` with non-default options
+pub async fn to_async_writer_with<W: AsyncWrite + Unpin>(
+    mut writer: W,
+    ele: &dyn JsonElement,
+    options: &SerializeOptions,
+) -> Result<()> {
+    let mut out = String::new();
+    write_element(ele, options, &mut out);
+    writer
+        .write_all(out.as_bytes())
+        .await
+        .map_err(write_error)?;
+    writer.flush().await.map_err(write_error)
+}
@@ -1,14 +1,12 @@
-use std::{collections::HashMap, ops::Index};
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, BuildHasherDefault, Hasher},
+    ops::Index,
+};
 
 use crate::Serializable;
 
-use super::{JsonError, JsonType, Result};
-
-macro_rules! jerr {
-    ($err:expr) => {
-        Err(JsonError::new($err.to_string()))
-    };
-}
+use super::{JsonError, JsonErrorKind, JsonType, RawNumber, Result, Span};
 
 ///JSON element
 ///
@@ -16,9 +14,9 @@ macro_rules! jerr {
 ///
 ///object() to get a JSON object
 ///
-///int() to get i32
+///int() to get i32, i64() and u64() for wider integers
 ///
-///float() to get f64
+///float() to get f64, f64() to get any number as f64
 ///
 ///bool() to get bool
 pub trait JsonElement {
@@ -27,29 +25,65 @@ pub trait JsonElement {
 
     /// get element as a JSON array
     fn array(&self) -> Result<&JsonArray> {
-        jerr!("JSON Element is not an array")
+        jerr!(TypeMismatch, "JSON Element is not an array")
     }
     /// get element as a JSON object
     fn object(&self) -> Result<&JsonObject> {
-        jerr!("JSON Element is not an object")
+        jerr!(TypeMismatch, "JSON Element is not an object")
+    }
+    /// get element as a mutable JSON array
+    fn array_mut(&mut self) -> Result<&mut JsonArray> {
+        jerr!(TypeMismatch, "JSON Element is not an array")
+    }
+    /// get element as a mutable JSON object
+    fn object_mut(&mut self) -> Result<&mut JsonObject> {
+        jerr!(TypeMismatch, "JSON Element is not an object")
     }
     fn primitive(&self) -> Result<&JsonPrimitive> {
-        jerr!("JSON Element is not a primitive")
+        jerr!(TypeMismatch, "JSON Element is not a primitive")
     }
+    /// get element as i32, larger integers are a `NumberOverflow`
     fn int(&self) -> Result<i32> {
-        jerr!("JSON Element is not a int")
+        jerr!(TypeMismatch, "JSON Element is not a int")
+    }
+
+    /// get element as i64, integers above `i64::MAX` are a `NumberOverflow`
+    fn i64(&self) -> Result<i64> {
+        jerr!(TypeMismatch, "JSON Element is not a int")
+    }
+
+    /// get element as u64, negative integers are a `NumberOverflow`
+    fn u64(&self) -> Result<u64> {
+        jerr!(TypeMismatch, "JSON Element is not a int")
     }
 
+    /// get element as f64, only if it was written with a fraction or exponent
     fn float(&self) -> Result<f64> {
-        jerr!("JSON Element is not a float")
+        jerr!(TypeMismatch, "JSON Element is not a float")
+    }
+
+    /// get any number as f64
+    fn f64(&self) -> Result<f64> {
+        jerr!(TypeMismatch, "JSON Element is not a number")
+    }
+
+    /// get a number kept as text, see `ParseOptions::raw_numbers`
+    fn raw_number(&self) -> Result<&RawNumber> {
+        jerr!(TypeMismatch, "JSON Element is not a raw number")
     }
 
     fn string(&self) -> Result<String> {
-        jerr!("JSON Element is not a string")
+        jerr!(TypeMismatch, "JSON Element is not a string")
     }
 
     fn bool(&self) -> Result<bool> {
-        jerr!("JSON Element is not a bool")
+        jerr!(TypeMismatch, "JSON Element is not a bool")
+    }
+
+    /// where the element was parsed from, only kept when parsing with
+    /// `ParseOptions::spans`
+    fn span(&self) -> Option<Span> {
+        None
     }
 }
 
@@ -82,25 +116,60 @@ impl Index<usize> for dyn JsonElement {
 ///len() to retrieve length of array
 pub struct JsonArray {
     children: Vec<Box<dyn JsonElement>>,
+    span: Option<Span>,
 }
 
 ///function as a JSON object
 ///
 ///get(key) to get item of key
+///
+///iter() to visit members in the order they were put
 pub struct JsonObject {
-    children: HashMap<String, Box<dyn JsonElement>>,
+    /// members in insertion order, with the span of the key, see
+    /// `key_span()`
+    children: Vec<(String, Box<dyn JsonElement>, Option<Span>)>,
+    /// position in `children` of the key with each hash. a key whose hash
+    /// is taken by another one is not in it and is searched for
+    index: HashMap<u64, usize, BuildHasherDefault<Prehashed>>,
+    hasher: RandomState,
+    span: Option<Span>,
+}
+
+/// hasher for `JsonObject::index`, whose keys are hashes already
+#[derive(Default)]
+struct Prehashed(u64);
+
+impl Hasher for Prehashed {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(b);
+        }
+    }
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
 }
 
 pub struct JsonPrimitive {
     value: JsonType,
+    span: Option<Span>,
 }
 impl JsonElement for JsonArray {
     fn array(&self) -> Result<&JsonArray> {
         Ok(self)
     }
+    fn array_mut(&mut self) -> Result<&mut JsonArray> {
+        Ok(self)
+    }
     fn get_type(&self) -> JsonType {
         JsonType::Array
     }
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
 }
 impl Index<usize> for JsonArray {
     type Output = Box<dyn JsonElement>;
@@ -114,9 +183,13 @@ impl JsonArray {
     pub fn new() -> Box<JsonArray> {
         JsonArray {
             children: Vec::new(),
+            span: None,
         }
         .into()
     }
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
     pub(crate) fn get_children(&self) -> &Vec<Box<dyn JsonElement>> {
         &self.children
     }
@@ -136,7 +209,13 @@ impl JsonArray {
     }
 
     pub fn get(&self, index: usize) -> Result<&dyn JsonElement> {
-        Ok(self.children[index].as_ref())
+        match self.children.get(index) {
+            Some(v) => Ok(v.as_ref()),
+            None => jerr!(
+                IndexOutOfRange,
+                format!("index {} is out of range for length {}", index, self.len())
+            ),
+        }
     }
 }
 
@@ -144,39 +223,134 @@ impl JsonElement for JsonObject {
     fn object(&self) -> Result<&JsonObject> {
         Ok(self)
     }
+    fn object_mut(&mut self) -> Result<&mut JsonObject> {
+        Ok(self)
+    }
     fn get_type(&self) -> JsonType {
         JsonType::Object
     }
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
 }
 impl Index<&str> for JsonObject {
     type Output = Box<dyn JsonElement>;
 
     fn index(&self, index: &str) -> &Self::Output {
-        self.children.get(index).unwrap()
+        match self.position(index) {
+            Some(i) => &self.children[i].1,
+            None => panic!("key \"{}\" is undefined", index),
+        }
     }
 }
 
 impl JsonObject {
     pub fn new() -> Box<JsonObject> {
         JsonObject {
-            children: HashMap::new(),
+            children: Vec::new(),
+            index: HashMap::default(),
+            hasher: RandomState::new(),
+            span: None,
         }
         .into()
     }
-    pub(crate) fn get_children(&self) -> &HashMap<String, Box<dyn JsonElement>> {
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+    pub(crate) fn get_children(&self) -> &[(String, Box<dyn JsonElement>, Option<Span>)] {
         &self.children
     }
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// members in the order their keys were first put
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &dyn JsonElement)> {
+        self.children
+            .iter()
+            .map(|(k, v, _)| (k.as_str(), v.as_ref()))
+    }
+
     pub fn put<T: Serializable>(&mut self, key: &str, item: T) {
-        self.children.insert(key.to_string(), item.serialize());
+        self.put_ele(key, item.serialize());
     }
+    /// a key that is already present keeps its place, only the value changes
     pub fn put_ele(&mut self, key: &str, item: Box<dyn JsonElement>) {
-        self.children.insert(key.to_string(), item);
+        self.put_spanned(key, item, None);
+    }
+
+    /// `put_ele` that also sets where the key was parsed from
+    pub(crate) fn put_spanned(
+        &mut self,
+        key: &str,
+        item: Box<dyn JsonElement>,
+        key_span: Option<Span>,
+    ) {
+        match self.position(key) {
+            Some(i) => {
+                let member = &mut self.children[i];
+                member.1 = item;
+                member.2 = key_span.or(member.2);
+            }
+            None => {
+                let hash = self.hasher.hash_one(key);
+                self.index.entry(hash).or_insert(self.children.len());
+                self.children.push((key.to_string(), item, key_span));
+            }
+        }
     }
 
     pub fn get(&self, key: &str) -> Result<&dyn JsonElement> {
-        match self.children.get(&key.to_string()) {
-            Some(v) => Ok(v.as_ref()),
-            None => jerr!(format!("key \"{}\" is undefined", key)),
+        match self.position(key) {
+            Some(i) => Ok(self.children[i].1.as_ref()),
+            None => jerr!(MissingKey, format!("key \"{}\" is undefined", key)),
+        }
+    }
+
+    /// where the key of a member was parsed from, only kept when parsing
+    /// with `ParseOptions::spans`
+    pub fn key_span(&self, key: &str) -> Option<Span> {
+        self.children[self.position(key)?].2
+    }
+
+    pub(crate) fn get_mut(&mut self, key: &str) -> Option<&mut Box<dyn JsonElement>> {
+        let i = self.position(key)?;
+        Some(&mut self.children[i].1)
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        let i = *self.index.get(&self.hasher.hash_one(key))?;
+        if self.children[i].0 == key {
+            return Some(i);
+        }
+        self.children.iter().position(|(k, _, _)| k == key)
+    }
+}
+
+impl Drop for JsonArray {
+    fn drop(&mut self) {
+        drop_children(std::mem::take(&mut self.children));
+    }
+}
+
+impl Drop for JsonObject {
+    fn drop(&mut self) {
+        drop_children(self.children.drain(..).map(|(_, v, _)| v).collect());
+    }
+}
+
+/// drop a tree with an explicit stack, every element is emptied before it
+/// is dropped so deep trees cannot overflow the call stack
+fn drop_children(mut stack: Vec<Box<dyn JsonElement>>) {
+    while let Some(mut ele) = stack.pop() {
+        if let Ok(arr) = ele.array_mut() {
+            stack.append(&mut arr.children);
+        } else if let Ok(object) = ele.object_mut() {
+            stack.extend(object.children.drain(..).map(|(_, v, _)| v));
         }
     }
 }
@@ -186,39 +360,58 @@ impl JsonElement for JsonPrimitive {
         Ok(self)
     }
     fn int(&self) -> Result<i32> {
-        match self.get_type() {
-            JsonType::Int(v) => Ok(v),
-            _ => jerr!("JSON Element is not a int"),
-        }
+        self.value.as_i32()
+    }
+
+    fn i64(&self) -> Result<i64> {
+        self.value.as_i64()
+    }
+
+    fn u64(&self) -> Result<u64> {
+        self.value.as_u64()
     }
 
     fn float(&self) -> Result<f64> {
-        match self.get_type() {
-            JsonType::Float(v) => Ok(v),
-            _ => jerr!("JSON Element is not a float"),
+        self.value.as_float()
+    }
+
+    fn f64(&self) -> Result<f64> {
+        self.value.as_f64()
+    }
+
+    fn raw_number(&self) -> Result<&RawNumber> {
+        match &self.value {
+            JsonType::RawNumber(v) => Ok(v),
+            _ => jerr!(TypeMismatch, "JSON Element is not a raw number"),
         }
     }
 
     fn string(&self) -> Result<String> {
         match self.get_type() {
             JsonType::String(v) => Ok(v),
-            _ => jerr!("JSON Element is not a string"),
+            _ => jerr!(TypeMismatch, "JSON Element is not a string"),
         }
     }
 
     fn bool(&self) -> Result<bool> {
         match self.get_type() {
             JsonType::Bool(v) => Ok(v),
-            _ => jerr!("JSON Element is not a bool"),
+            _ => jerr!(TypeMismatch, "JSON Element is not a bool"),
         }
     }
     fn get_type(&self) -> JsonType {
         self.value.clone()
     }
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
 }
 
 impl JsonPrimitive {
     pub(crate) fn new(value: JsonType) -> Box<JsonPrimitive> {
-        JsonPrimitive { value }.into()
+        JsonPrimitive { value, span: None }.into()
+    }
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
     }
 }
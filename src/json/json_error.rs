@@ -1,19 +1,163 @@
-use std::fmt;
+use std::{error::Error, fmt, sync::Arc};
 pub type Result<T> = std::result::Result<T, JsonError>;
 
+/// what went wrong, for matching on failures without reading the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// malformed JSON text
+    Syntax,
+    /// the input ended in the middle of a value
+    UnexpectedEof,
+    /// an accessor was called on an element of another type
+    TypeMismatch,
+    /// an object has no member with the requested key
+    MissingKey,
+    /// an array has no element at the requested index
+    IndexOutOfRange,
+    /// a number does not fit the requested numeric type
+    NumberOverflow,
+    /// arrays and objects are nested deeper than `ParseOptions::max_depth`
+    DepthLimit,
+    /// an object repeats a key, see `DuplicateKeys::Error`
+    DuplicateKey,
+    /// the input is longer than `ParseLimits::max_input_bytes`
+    InputTooLarge,
+    /// a string is longer than `ParseLimits::max_string_length`
+    StringTooLong,
+    /// an object has more members than `ParseLimits::max_object_members`
+    TooManyMembers,
+    /// an array has more elements than `ParseLimits::max_array_elements`
+    TooManyElements,
+    /// the document has more values than `ParseLimits::max_nodes`
+    TooManyNodes,
+    /// reading the input failed, the I/O error is the `source()`
+    Io,
+    /// raised by user code, such as a `JsonVisitor` stopping the parse
+    Custom,
+}
+
+/// location in the input, line and column start at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// byte offset from the start of the input
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Position {
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new(0, 1, 1)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+/// where an element or key was parsed from, see `ParseOptions::spans`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// position of the first byte
+    pub start: Position,
+    /// position after the last byte
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Span {
+        Span { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to {}", self.start, self.end)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct JsonError {
+    kind: JsonErrorKind,
     message: String,
+    position: Option<Position>,
+    source: Option<Arc<dyn Error + Send + Sync>>,
 }
 
 impl JsonError {
-    pub(crate) fn new(messeage: String) -> JsonError {
-        JsonError { message: messeage }
+    pub fn new(kind: JsonErrorKind, message: impl Into<String>) -> JsonError {
+        JsonError {
+            kind,
+            message: message.into(),
+            position: None,
+            source: None,
+        }
+    }
+
+    /// attach the location the error refers to
+    pub fn at(mut self, position: Position) -> JsonError {
+        self.position = Some(position);
+        self
+    }
+
+    /// attach the underlying error, returned by `source()`
+    pub fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> JsonError {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn kind(&self) -> JsonErrorKind {
+        self.kind
+    }
+
+    /// the message without the location
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.position.map(|p| p.offset)
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|p| p.line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|p| p.column)
     }
 }
 
 impl fmt::Display for JsonError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self.position {
+            Some(position) => write!(f, "{} at {}", self.message, position),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
     }
 }
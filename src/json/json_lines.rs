@@ -0,0 +1,180 @@
+use std::io::{BufRead, Write};
+
+use super::{
+    to_json_with, JsonElement, JsonError, JsonErrorKind, JsonParser, ParseOptions, Position,
+    Result, Serializable, SerializeOptions,
+};
+
+///reads JSON Lines, one value per line
+///
+///iterate it to get the values, errors carry the line number and the
+///byte offset from the start of the input
+///
+///skip_blank() and skip_invalid() to leave out lines instead of failing
+pub struct JsonLinesReader<R> {
+    reader: R,
+    options: ParseOptions,
+    skip_blank: bool,
+    skip_invalid: bool,
+    buffer: Vec<u8>,
+    /// number of the next line, starting at 1
+    line: usize,
+    /// byte offset of the next line
+    offset: usize,
+    done: bool,
+}
+
+impl<R: BufRead> JsonLinesReader<R> {
+    /// read JSON Lines from `reader`
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonLinesReader,Result};
+    /// fn main() -> Result<()>{
+    ///     let log: &[u8] = b"{\"player\":\"Kirito\"}\n{\"player\":\"Asuna\"}\n";
+    ///     for entry in JsonLinesReader::new(log) {
+    ///         println!("{}", entry?["player"].string()?);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(reader: R) -> JsonLinesReader<R> {
+        JsonLinesReader::with_options(reader, &ParseOptions::default())
+    }
+
+    /// read JSON Lines, parsing every line with `options`
+    pub fn with_options(reader: R, options: &ParseOptions) -> JsonLinesReader<R> {
+        JsonLinesReader {
+            reader,
+            options: options.clone(),
+            skip_blank: false,
+            skip_invalid: false,
+            buffer: Vec::new(),
+            line: 1,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// leave out lines that are empty or only whitespace instead of
+    /// returning an error for them
+    pub fn skip_blank(mut self, skip: bool) -> Self {
+        self.skip_blank = skip;
+        self
+    }
+
+    /// leave out lines that are not valid JSON instead of returning an
+    /// error for them, blank lines included. I/O errors are still returned
+    pub fn skip_invalid(mut self, skip: bool) -> Self {
+        self.skip_invalid = skip;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// parse the line in `buffer`, errors are moved to its place in the input
+    fn parse_line(&self, position: Position) -> Result<Box<dyn JsonElement>> {
+        let mut line = &self.buffer[..];
+        if let Some(rest) = line.strip_suffix(b"\n") {
+            line = rest.strip_suffix(b"\r").unwrap_or(rest);
+        }
+        let result = match std::str::from_utf8(line) {
+            Ok(line) => JsonParser::parse_with(line, &self.options),
+            Err(err) => {
+                let at = err.valid_up_to();
+                let position = Position::new(at, 1, at + 1);
+                Err(JsonError::new(JsonErrorKind::Syntax, "Invalid UTF-8").at(position))
+            }
+        };
+        result.map_err(|err| match err.position() {
+            Some(p) => {
+                let offset = position.offset + p.offset;
+                err.at(Position::new(offset, position.line, p.column))
+            }
+            None => err.at(position),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesReader<R> {
+    type Item = Result<Box<dyn JsonElement>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let position = Position::new(self.offset, self.line, 1);
+            self.buffer.clear();
+            let read = match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(read) => read,
+                Err(err) => {
+                    self.done = true;
+                    let message = format!("Failed to read JSON: {}", err);
+                    let err = JsonError::new(JsonErrorKind::Io, message).with_source(err);
+                    return Some(Err(err.at(position)));
+                }
+            };
+            self.line += 1;
+            self.offset += read;
+            if self.skip_blank && self.buffer.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match self.parse_line(position) {
+                Err(_) if self.skip_invalid => continue,
+                result => return Some(result),
+            }
+        }
+        None
+    }
+}
+
+///writes JSON Lines, every value compact on a line of its own
+pub struct JsonLinesWriter<W> {
+    writer: W,
+    options: SerializeOptions,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    /// write JSON Lines to `writer`
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonLinesWriter,Result};
+    /// fn main() -> Result<()>{
+    ///     let mut writer = JsonLinesWriter::new(Vec::new());
+    ///     writer.write(vec![1, 2])?;
+    ///     writer.write(String::from("Link Start!"))?;
+    ///     assert_eq!(writer.into_inner(), b"[1,2]\n\"Link Start!\"\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(writer: W) -> JsonLinesWriter<W> {
+        JsonLinesWriter {
+            writer,
+            options: SerializeOptions {
+                compact: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// write `value` and a newline
+    pub fn write<T: Serializable>(&mut self, value: T) -> Result<()> {
+        let mut line = to_json_with(value, &self.options);
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).map_err(write_error)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(write_error)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub(crate) fn write_error(err: std::io::Error) -> JsonError {
+    JsonError::new(JsonErrorKind::Io, format!("Failed to write JSON: {}", err)).with_source(err)
+}
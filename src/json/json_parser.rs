@@ -1,15 +1,31 @@
-use std::collections::VecDeque;
+use std::{collections::HashMap, io::Read, ops::Range};
 
 use super::{
-    JsonArray, JsonElement, JsonError, JsonObject, JsonPrimitive, JsonType, Lexer, Result, Token,
-    TokenType,
+    encoding::decode, raw_number::is_json_number, DuplicateKeys, JsonArray, JsonElement, JsonError,
+    JsonErrorKind, JsonEvent, JsonObject, JsonPrimitive, JsonPullParser, JsonType, JsonValue,
+    JsonVisitor, Lexer, ParseOptions, Position, RawNumber, Result, Source, Span, Token, TokenType,
 };
+/// a value with the byte range it was parsed from
+pub(crate) type SpannedValue = (Box<dyn JsonElement>, Range<usize>);
+
+///recursive descent parser, tokens are pulled from the lexer one at a time
+///with a single token of lookahead, so nothing but the tree is allocated
+///for the whole document
 pub struct JsonParser<'a> {
-    tokens: &'a mut VecDeque<Token>,
-    diagnostic: Vec<String>,
+    lexer: Lexer<'a>,
+    lookahead: Option<Token<'a>>,
+    diagnostic: Vec<JsonError>,
+    options: ParseOptions,
+    /// arrays and objects currently open
+    depth: usize,
+    /// values parsed so far in the current document, see
+    /// `ParseLimits::max_nodes`
+    nodes: usize,
+    /// end of the token last taken with `next()`
+    last_end: Position,
 }
 
-impl JsonParser<'_> {
+impl<'a> JsonParser<'a> {
     /// parse a JSON string
     ///
     /// # Examples
@@ -30,53 +46,424 @@ impl JsonParser<'_> {
     /// }
     /// ```
     pub fn parse(input: &str) -> Result<Box<dyn JsonElement>> {
-        let mut lexer = Lexer::new(input.to_string());
-        let (tokens, diagnostic) = lexer.lex();
-        if !diagnostic.is_empty() {
-            return Err(JsonError::new(diagnostic.first().unwrap().to_string()));
-        }
-        let mut parser: JsonParser = JsonParser {
-            tokens,
-            diagnostic: vec![],
+        JsonParser::parse_with(input, &ParseOptions::default())
+    }
+
+    /// parse a JSON string with non-default options
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{to_json,JsonParser,ParseOptions,Result};
+    /// fn main() -> Result<()>{
+    ///     let options = ParseOptions {
+    ///         raw_numbers: true,
+    ///         ..Default::default()
+    ///     };
+    ///     let result = JsonParser::parse_with("[0.1000000000000000055]", &options)?;
+    ///     assert_eq!(result[0].raw_number()?.as_str(), "0.1000000000000000055");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Box<dyn JsonElement>> {
+        let lexer = Lexer::new(Source::slice(input.as_bytes()));
+        JsonParser::new(lexer, options).finish_first_error()
+    }
+
+    /// parse a JSON5 string, such as a hand-edited config file
+    ///
+    /// comments, trailing commas, single-quoted strings, unquoted keys,
+    /// hexadecimal numbers, numbers with a leading or trailing decimal
+    /// point, `Infinity` and `NaN` are accepted. unquoted keys are limited
+    /// to ASCII letters, digits, `_` and `$`
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let config = "{
+    ///         // the floor the players are on
+    ///         floor: 0x4A,
+    ///         boss: 'Gleam Eyes',
+    ///         hp: .5,
+    ///     }";
+    ///     let result = JsonParser::parse_json5(config)?;
+    ///     assert_eq!(result["floor"].int()?, 74);
+    ///     assert_eq!(result["boss"].string()?, "Gleam Eyes");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_json5(input: &str) -> Result<Box<dyn JsonElement>> {
+        let options = ParseOptions {
+            json5: true,
+            ..Default::default()
         };
+        JsonParser::parse_with(input, &options)
+    }
 
-        let json = parser.decide_parse();
-        parser.tmatch(TokenType::Eof);
-        if !parser.diagnostic.is_empty() {
-            return Err(JsonError::new(
-                parser.diagnostic.first().unwrap().to_string(),
-            ));
+    /// parse JSON from raw bytes
+    ///
+    /// a UTF-8 BOM is skipped. UTF-16 and UTF-32 in either byte order are
+    /// told apart by their BOM, or by the zero bytes of the first two
+    /// characters as RFC 4627 describes, and transcoded to UTF-8.
+    /// invalid UTF-8 is an error at the offset of its first bad byte.
+    ///
+    /// error offsets count bytes of `input` for UTF-8, and bytes of the
+    /// transcoded text for UTF-16 and UTF-32 outside of encoding errors.
+    /// spans count bytes of the text after any BOM
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let utf16: Vec<u8> = [0xfeff]
+    ///         .into_iter()
+    ///         .chain("[\"Asuna\"]".encode_utf16())
+    ///         .flat_map(u16::to_le_bytes)
+    ///         .collect();
+    ///     let result = JsonParser::parse_bytes(&utf16)?;
+    ///     assert_eq!(result[0].string()?, "Asuna");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_bytes(input: &[u8]) -> Result<Box<dyn JsonElement>> {
+        JsonParser::parse_bytes_with(input, &ParseOptions::default())
+    }
+
+    /// parse JSON from raw bytes with non-default options
+    ///
+    /// input longer than `ParseLimits::max_input_bytes` is rejected before
+    /// it is decoded, the error has no position
+    pub fn parse_bytes_with(input: &[u8], options: &ParseOptions) -> Result<Box<dyn JsonElement>> {
+        let max = options.limits.max_input_bytes;
+        if input.len() > max {
+            let message = format!("Input is larger than {} bytes", max);
+            return Err(JsonError::new(JsonErrorKind::InputTooLarge, message));
+        }
+        let (text, skipped) = decode(input)?;
+        JsonParser::parse_with(&text, options).map_err(|err| match err.position() {
+            Some(p) if skipped > 0 => {
+                let position = Position::new(p.offset + skipped, p.line, p.column);
+                err.at(position)
+            }
+            _ => err,
+        })
+    }
+
+    /// parse the JSON value at the start of `input`, returns it with the
+    /// number of bytes it takes up. whatever follows it is not looked at
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let frame = "{\"floor\":74} trailing bytes";
+    ///     let (result, consumed) = JsonParser::parse_prefix(frame)?;
+    ///     assert_eq!(result["floor"].int()?, 74);
+    ///     assert_eq!(&frame[consumed..], " trailing bytes");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_prefix(input: &str) -> Result<(Box<dyn JsonElement>, usize)> {
+        let lexer = Lexer::new(Source::slice(input.as_bytes()));
+        let mut parser = JsonParser::new(lexer, &ParseOptions::default());
+        match parser.parse_next() {
+            Some(result) => result.map(|(json, range)| (json, range.end)),
+            None => Err(unexpected_token(&parser.next())),
         }
-        Ok(json)
     }
 
-    fn tmatch(&mut self, ttype: TokenType) -> Token {
+    /// parse JSON read from `reader`
+    ///
+    /// bytes are pulled from the reader as the parser needs them, so the
+    /// document is never held in memory as a whole. reads go through an
+    /// internal buffer, there is no need to wrap the reader in a `BufReader`
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let file: &[u8] = b"{\"player\":\"Asuna\"}";
+    ///     let result = JsonParser::from_reader(file)?;
+    ///     println!("{}",result["player"].string()?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Box<dyn JsonElement>> {
+        JsonParser::from_reader_with(reader, &ParseOptions::default())
+    }
+
+    /// parse JSON read from `reader` with non-default options
+    pub fn from_reader_with<R: Read>(
+        mut reader: R,
+        options: &ParseOptions,
+    ) -> Result<Box<dyn JsonElement>> {
+        let lexer = Lexer::new(Source::reader(&mut reader));
+        JsonParser::new(lexer, options).finish_first_error()
+    }
+
+    fn tmatch(&mut self, ttype: TokenType) -> Token<'a> {
         let token = self.next();
         if token.token_type == ttype {
             token
         } else {
             let pos = token.position;
-            if token.token_type == TokenType::Eof {
-                self.diagnostic.push("Unexpected end of JSON".to_string());
-            } else {
-                self.diagnostic.push(format!(
-                    "Unexpected token {} at position {}",
-                    token.text, pos
-                ));
-            }
-            self.tokens.push_front(token);
+            self.unexpected(&token);
+            self.unread(token);
             Token::new(ttype, "", pos)
         }
     }
+    /// parse a JSON string, recovering from errors instead of stopping at
+    /// the first one
+    ///
+    /// returns the partially parsed tree together with every error in input
+    /// order, invalid values are null in the tree and missing ones are left out
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::JsonParser;
+    /// let (result, errors) = JsonParser::parse_all_errors("[1, tru, 3 4]");
+    /// assert_eq!(result.array().unwrap().len(), 3);
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    pub fn parse_all_errors(input: &str) -> (Box<dyn JsonElement>, Vec<JsonError>) {
+        let lexer = Lexer::recovering(Source::slice(input.as_bytes()));
+        JsonParser::new(lexer, &ParseOptions::default()).finish()
+    }
+
+    /// parse a JSON string, calling `visitor` for every value instead of
+    /// building the tree
+    ///
+    /// an error returned by the visitor stops parsing and is returned as is,
+    /// with the position of the value it was called for if it has none
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonParser,JsonVisitor,Result};
+    /// struct Sum(i64);
+    /// impl JsonVisitor for Sum {
+    ///     fn visit_int(&mut self, value: i64) -> Result<()> {
+    ///         self.0 += value;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// fn main() -> Result<()>{
+    ///     let mut sum = Sum(0);
+    ///     JsonParser::parse_with_visitor("[1,{\"hp\":2},[3]]", &mut sum)?;
+    ///     assert_eq!(sum.0, 6);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_with_visitor<V: JsonVisitor + ?Sized>(input: &str, visitor: &mut V) -> Result<()> {
+        JsonParser::parse_with_visitor_with(input, &ParseOptions::default(), visitor)
+    }
+
+    /// `parse_with_visitor` with `options`, see `JsonPullParser::with_options`
+    /// for the ones that apply
+    pub fn parse_with_visitor_with<V: JsonVisitor + ?Sized>(
+        input: &str,
+        options: &ParseOptions,
+        visitor: &mut V,
+    ) -> Result<()> {
+        for event in JsonPullParser::with_options(input, options.clone()) {
+            let (event, position) = event?;
+            let visited = match event {
+                JsonEvent::StartObject => visitor.visit_object_start(),
+                JsonEvent::EndObject => visitor.visit_object_end(),
+                JsonEvent::StartArray => visitor.visit_array_start(),
+                JsonEvent::EndArray => visitor.visit_array_end(),
+                JsonEvent::Key(key) => visitor.visit_key(&key),
+                JsonEvent::String(v) => visitor.visit_string(&v),
+                JsonEvent::Int(v) => visitor.visit_int(v),
+                JsonEvent::UInt(v) => visitor.visit_uint(v),
+                JsonEvent::Float(v) => visitor.visit_float(v),
+                JsonEvent::RawNumber(v) => visitor.visit_raw_number(&v),
+                JsonEvent::Bool(v) => visitor.visit_bool(v),
+                JsonEvent::Null => visitor.visit_null(),
+            };
+            if let Err(err) = visited {
+                return Err(match err.position() {
+                    Some(_) => err,
+                    None => err.at(position),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// parse a JSON string into a `JsonValue` that borrows from `input`
+    ///
+    /// strings without escapes are not copied, which saves most of the
+    /// allocations of `parse` for small documents
+    ///
+    /// # Examples
+    /// ```
+    /// use std::borrow::Cow;
+    /// use rjson::{JsonParser,JsonValue,Result};
+    /// fn main() -> Result<()>{
+    ///     let json = String::from("{\"player\":\"Asuna\",\"quote\":\"\\\"Link Start!\\\"\"}");
+    ///     let result = JsonParser::parse_borrowed(&json)?;
+    ///     assert!(matches!(result["player"], JsonValue::String(Cow::Borrowed("Asuna"))));
+    ///     assert_eq!(result["quote"].string()?, "\"Link Start!\"");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_borrowed(input: &'a str) -> Result<JsonValue<'a>> {
+        let lexer = Lexer::new(Source::slice(input.as_bytes()));
+        let mut parser = JsonParser::new(lexer, &ParseOptions::default());
+        let value = parser.parse_borrowed_value()?;
+        let token = parser.next_checked()?;
+        if token.token_type != TokenType::Eof {
+            return Err(unexpected_token(&token));
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn new(mut lexer: Lexer<'a>, options: &ParseOptions) -> JsonParser<'a> {
+        lexer.set_options(options);
+        JsonParser {
+            lexer,
+            lookahead: None,
+            diagnostic: vec![],
+            options: options.clone(),
+            depth: 0,
+            nodes: 0,
+            last_end: Position::default(),
+        }
+    }
+
+    /// parse the whole input as one value, returns it with the errors of
+    /// both lexer and parser in input order
+    fn finish(mut self) -> (Box<dyn JsonElement>, Vec<JsonError>) {
+        let json = self.decide_parse();
+        self.tmatch(TokenType::Eof);
+        let errors = self.take_errors();
+        (json, errors)
+    }
+
+    /// the errors of both lexer and parser so far, in input order
+    fn take_errors(&mut self) -> Vec<JsonError> {
+        let mut errors = std::mem::take(self.lexer.diagnostic());
+        errors.append(&mut self.diagnostic);
+        errors.sort_by_key(|e| e.offset());
+        errors
+    }
+
+    /// parse the next of several values written one after another, with
+    /// the byte range it spans. `None` once only whitespace is left
+    pub(crate) fn parse_next(&mut self) -> Option<Result<SpannedValue>> {
+        if self.peek() == TokenType::Eof {
+            // a stopping lexer ends the input at its first error
+            return self.take_errors().into_iter().next().map(Err);
+        }
+        let start = self.lookahead.as_ref().unwrap().position.offset;
+        // the limits hold for each value, not for the whole stream
+        self.nodes = 0;
+        let json = self.decide_parse();
+        let end = self.lexer.offset();
+        Some(match self.take_errors().into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok((json, start..end)),
+        })
+    }
+
+    fn finish_first_error(self) -> Result<Box<dyn JsonElement>> {
+        let (json, errors) = self.finish();
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(json),
+        }
+    }
+
     fn decide_parse(&mut self) -> Box<dyn JsonElement> {
+        if self.nodes >= self.options.limits.max_nodes {
+            return self.skip_too_many_nodes();
+        }
+        self.nodes += 1;
         let token = self.peek();
         match token {
-            TokenType::LCurlyBracket => self.parse_object(),
-            TokenType::LBracket => self.parse_array(),
-            TokenType::Int | TokenType::Float | TokenType::String => self.parse_primitive(),
+            TokenType::LCurlyBracket | TokenType::LBracket => {
+                if self.depth >= self.options.max_depth {
+                    return self.skip_too_deep();
+                }
+                self.depth += 1;
+                let start = self.peek_position();
+                let ele: Box<dyn JsonElement> = if token == TokenType::LBracket {
+                    let mut array = self.parse_array();
+                    if self.options.spans {
+                        array.set_span(Span::new(start, self.last_end));
+                    }
+                    array
+                } else {
+                    let mut object = self.parse_object();
+                    if self.options.spans {
+                        object.set_span(Span::new(start, self.last_end));
+                    }
+                    object
+                };
+                self.depth -= 1;
+                ele
+            }
             _ => self.parse_primitive(),
         }
     }
+
+    /// report an array or object nested too deep and skip it without
+    /// recursing into it
+    fn skip_too_deep(&mut self) -> Box<dyn JsonElement> {
+        let token = self.next();
+        self.diagnostic
+            .push(too_deep(&token, self.options.max_depth));
+        self.skip_to_close();
+        JsonPrimitive::new(JsonType::Null)
+    }
+
+    /// skip a value once the document has `ParseLimits::max_nodes` values,
+    /// the limit is reported for the first value skipped
+    fn skip_too_many_nodes(&mut self) -> Box<dyn JsonElement> {
+        let max = self.options.limits.max_nodes;
+        if self.nodes == max {
+            self.nodes += 1;
+            let message = format!("JSON has more than {} values", max);
+            let err = JsonError::new(JsonErrorKind::TooManyNodes, message);
+            let position = self.peek_position();
+            self.diagnostic.push(err.at(position));
+        }
+        match self.peek() {
+            TokenType::LBracket | TokenType::LCurlyBracket => {
+                self.next();
+                self.skip_to_close();
+            }
+            TokenType::Eof => {}
+            _ => {
+                self.next();
+            }
+        }
+        JsonPrimitive::new(JsonType::Null)
+    }
+
+    /// skip tokens up to and including the bracket that closes the array
+    /// or object just opened
+    fn skip_to_close(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                TokenType::Eof => break,
+                TokenType::LBracket | TokenType::LCurlyBracket => depth += 1,
+                TokenType::RBracket | TokenType::RCurlyBracket => depth -= 1,
+                _ => {}
+            }
+            self.next();
+        }
+    }
+
+    /// report a member past a `ParseLimits` bound and skip the rest of
+    /// the array or object it is in
+    fn skip_too_many(&mut self, kind: JsonErrorKind, message: String) {
+        let position = self.peek_position();
+        self.diagnostic
+            .push(JsonError::new(kind, message).at(position));
+        self.skip_to_close();
+    }
     fn parse_array(&mut self) -> Box<JsonArray> {
         self.tmatch(TokenType::LBracket);
         let mut arr = JsonArray::new();
@@ -84,10 +471,19 @@ impl JsonParser<'_> {
             self.tmatch(TokenType::RBracket);
             return arr;
         }
-        arr.push_ele(self.decide_parse());
-        while !self.cmp_type(TokenType::Eof) && !self.cmp_type(TokenType::RBracket) {
-            self.tmatch(TokenType::Comma);
-            arr.push_ele(self.decide_parse());
+        let max = self.options.limits.max_array_elements;
+        loop {
+            if arr.len() == max {
+                let message = format!("Array has more than {} elements", max);
+                self.skip_too_many(JsonErrorKind::TooManyElements, message);
+                return arr;
+            }
+            if let Some(ele) = self.parse_value() {
+                arr.push_ele(ele);
+            }
+            if !self.next_member(TokenType::RBracket) {
+                break;
+            }
         }
         self.tmatch(TokenType::RBracket);
         arr
@@ -99,50 +495,392 @@ impl JsonParser<'_> {
             self.tmatch(TokenType::RCurlyBracket);
             return object;
         }
-        let (key, field) = self.parse_key_field();
-        object.put_ele(&key, field);
-        while !self.cmp_type(TokenType::Eof) && !self.cmp_type(TokenType::RCurlyBracket) {
-            self.tmatch(TokenType::Comma);
-            let (key, field) = self.parse_key_field();
-            object.put_ele(&key, field);
+        // first position of each key, kept for the policies that need it
+        let mut seen = HashMap::new();
+        let max = self.options.limits.max_object_members;
+        let mut members = 0;
+        loop {
+            if members == max {
+                let message = format!("Object has more than {} members", max);
+                self.skip_too_many(JsonErrorKind::TooManyMembers, message);
+                return object;
+            }
+            members += 1;
+            if let Some((key, span, field)) = self.parse_key_field() {
+                let options = &self.options;
+                if let Err(err) = put_member(options, &mut object, &mut seen, key, span, field) {
+                    self.diagnostic.push(err);
+                }
+            }
+            if !self.next_member(TokenType::RCurlyBracket) {
+                break;
+            }
         }
         self.tmatch(TokenType::RCurlyBracket);
         object
     }
-    fn parse_key_field(&mut self) -> (String, Box<dyn JsonElement>) {
-        let key = self.tmatch(TokenType::String);
-        self.tmatch(TokenType::Colon);
-        let field = self.decide_parse();
-        (key.text, field)
+    fn parse_key_field(&mut self) -> Option<(String, Span, Box<dyn JsonElement>)> {
+        self.peek();
+        let token = self.lookahead.as_ref().unwrap();
+        let json5_key = self.options.json5 && token.is_identifier();
+        if token.token_type != TokenType::String && !json5_key {
+            self.unexpected_peek();
+            return None;
+        }
+        let key = self.next();
+        if self.cmp_type(TokenType::Colon) {
+            self.next();
+        } else {
+            self.unexpected_peek();
+        }
+        let field = self.parse_value()?;
+        let span = Span::new(key.position, key.end);
+        Some((key.text.into_owned(), span, field))
+    }
+
+    /// parse a value, a missing value is reported without consuming
+    /// the token that ends it
+    fn parse_value(&mut self) -> Option<Box<dyn JsonElement>> {
+        match self.peek() {
+            TokenType::Comma | TokenType::RBracket | TokenType::RCurlyBracket | TokenType::Eof => {
+                self.unexpected_peek();
+                None
+            }
+            _ => Some(self.decide_parse()),
+        }
+    }
+
+    /// consume the comma before the next member of an array or object,
+    /// returns false at the closing bracket. in JSON5 the comma may also
+    /// come right before it.
+    /// anything else is reported and skipped up to the next comma or `close`
+    fn next_member(&mut self, close: TokenType) -> bool {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                TokenType::Eof => return false,
+                TokenType::Comma if depth == 0 => {
+                    self.next();
+                    return !(self.options.json5 && self.cmp_type(close));
+                }
+                t if t == close && depth == 0 => return false,
+                t => {
+                    let token = self.next();
+                    if depth == 0 {
+                        self.unexpected(&token);
+                    }
+                    match t {
+                        TokenType::LBracket | TokenType::LCurlyBracket => depth += 1,
+                        TokenType::RBracket | TokenType::RCurlyBracket if depth > 0 => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
     }
 
     fn parse_primitive(&mut self) -> Box<JsonPrimitive> {
         let token = self.next();
-        JsonPrimitive::new(match token.token_type {
-            TokenType::Int => JsonType::Int(token.text.parse().unwrap()),
-            TokenType::Float => JsonType::Float(token.text.parse().unwrap()),
-            TokenType::String => JsonType::String(token.text),
-            TokenType::Bool => JsonType::Bool(token.text.parse().unwrap()),
-            TokenType::Null => JsonType::Null,
-            _ => unreachable!(),
+        let span = Span::new(token.position, token.end);
+        let mut primitive = JsonPrimitive::new(match token.token_type {
+            TokenType::Int
+            | TokenType::Float
+            | TokenType::NonFinite
+            | TokenType::String
+            | TokenType::Bool
+            | TokenType::Null => match primitive_value(token, &self.options) {
+                Ok(v) => v,
+                Err(err) => {
+                    self.diagnostic.push(err);
+                    JsonType::Null
+                }
+            },
+            _ => {
+                self.unexpected(&token);
+                if token.token_type == TokenType::Eof {
+                    self.unread(token);
+                }
+                JsonType::Null
+            }
+        });
+        if self.options.spans {
+            primitive.set_span(span);
+        }
+        primitive
+    }
+
+    fn parse_borrowed_value(&mut self) -> Result<JsonValue<'a>> {
+        let token = self.next_checked()?;
+        if let TokenType::LBracket | TokenType::LCurlyBracket = token.token_type {
+            if self.depth >= self.options.max_depth {
+                return Err(too_deep(&token, self.options.max_depth));
+            }
+            self.depth += 1;
+            let value = self.parse_borrowed_container(token);
+            self.depth -= 1;
+            return value;
+        }
+        Ok(match token.token_type {
+            TokenType::String => JsonValue::String(token.text),
+            _ => match primitive_value(token, &self.options)? {
+                JsonType::Int(v) => JsonValue::Int(v),
+                JsonType::UInt(v) => JsonValue::UInt(v),
+                JsonType::Float(v) => JsonValue::Float(v),
+                JsonType::Bool(v) => JsonValue::Bool(v),
+                _ => JsonValue::Null,
+            },
         })
     }
 
-    fn cmp_type(&self, ttype: TokenType) -> bool {
+    /// the array or object `token` opens
+    fn parse_borrowed_container(&mut self, token: Token<'a>) -> Result<JsonValue<'a>> {
+        if token.token_type == TokenType::LBracket {
+            let mut arr = vec![];
+            if self.cmp_type(TokenType::RBracket) {
+                self.next();
+                return Ok(JsonValue::Array(arr));
+            }
+            loop {
+                arr.push(self.parse_borrowed_value()?);
+                if !self.borrowed_next_member(TokenType::RBracket)? {
+                    return Ok(JsonValue::Array(arr));
+                }
+            }
+        }
+        let mut object = vec![];
+        if self.cmp_type(TokenType::RCurlyBracket) {
+            self.next();
+            return Ok(JsonValue::Object(object));
+        }
+        loop {
+            let key = self.next_checked()?;
+            if key.token_type != TokenType::String {
+                return Err(unexpected_token(&key));
+            }
+            let colon = self.next_checked()?;
+            if colon.token_type != TokenType::Colon {
+                return Err(unexpected_token(&colon));
+            }
+            object.push((key.text, self.parse_borrowed_value()?));
+            if !self.borrowed_next_member(TokenType::RCurlyBracket)? {
+                return Ok(JsonValue::Object(object));
+            }
+        }
+    }
+
+    /// consume the comma or `close` after a member, true if another member follows
+    fn borrowed_next_member(&mut self, close: TokenType) -> Result<bool> {
+        let token = self.next_checked()?;
+        match token.token_type {
+            TokenType::Comma => Ok(true),
+            t if t == close => Ok(false),
+            _ => Err(unexpected_token(&token)),
+        }
+    }
+
+    /// the next token, or the first error of the lexer
+    fn next_checked(&mut self) -> Result<Token<'a>> {
+        let token = self.next();
+        match self.lexer.diagnostic().first() {
+            Some(err) => Err(err.clone()),
+            None => Ok(token),
+        }
+    }
+
+    fn unexpected_peek(&mut self) {
+        let token = self.next();
+        self.unexpected(&token);
+        self.unread(token);
+    }
+
+    /// report an unexpected token once, the lexer has already reported
+    /// error tokens
+    fn unexpected(&mut self, token: &Token) {
+        let reported = self.diagnostic.last().and_then(|e| e.offset());
+        if token.token_type == TokenType::Error || reported == Some(token.position.offset) {
+            return;
+        }
+        self.diagnostic.push(unexpected_token(token));
+    }
+
+    fn cmp_type(&mut self, ttype: TokenType) -> bool {
         self.peek() == ttype
     }
 
-    fn peek(&self) -> TokenType {
-        match self.tokens.front() {
-            Some(t) => t.token_type,
-            None => TokenType::Error,
+    fn peek(&mut self) -> TokenType {
+        if self.lookahead.is_none() {
+            self.lookahead = Some(self.lexer.next_token());
         }
+        self.lookahead.as_ref().unwrap().token_type
+    }
+
+    /// position of the next token
+    fn peek_position(&mut self) -> Position {
+        self.peek();
+        self.lookahead.as_ref().unwrap().position
     }
 
-    fn next(&mut self) -> Token {
-        match self.tokens.pop_front() {
+    fn next(&mut self) -> Token<'a> {
+        let token = match self.lookahead.take() {
             Some(t) => t,
-            None => Token::new(TokenType::Error, "", 0),
+            None => self.lexer.next_token(),
+        };
+        self.last_end = token.end;
+        token
+    }
+
+    /// put back the token just taken with `next()`
+    fn unread(&mut self, token: Token<'a>) {
+        debug_assert!(self.lookahead.is_none());
+        self.lookahead = Some(token);
+    }
+}
+
+/// the value of a primitive token
+pub(crate) fn primitive_value(token: Token, options: &ParseOptions) -> Result<JsonType> {
+    Ok(match token.token_type {
+        TokenType::Int | TokenType::Float | TokenType::NonFinite => {
+            match number_value(&token, options)? {
+                Number::Int(v) => JsonType::Int(v),
+                Number::UInt(v) => JsonType::UInt(v),
+                Number::Float(v) => JsonType::Float(v),
+                Number::Raw(v) => JsonType::RawNumber(v),
+            }
+        }
+        TokenType::String => JsonType::String(token.text.into_owned()),
+        TokenType::Bool => JsonType::Bool(token.text.parse().unwrap()),
+        TokenType::Null => JsonType::Null,
+        _ => return Err(unexpected_token(&token)),
+    })
+}
+
+/// a number token converted as `ParseOptions` ask for
+pub(crate) enum Number {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Raw(RawNumber),
+}
+
+/// the value of an `Int`, `Float` or `NonFinite` token, other tokens are
+/// unexpected
+pub(crate) fn number_value(token: &Token, options: &ParseOptions) -> Result<Number> {
+    Ok(match token.token_type {
+        TokenType::Int | TokenType::Float
+            if options.raw_numbers && is_json_number(token.text.as_bytes()) =>
+        {
+            Number::Raw(RawNumber::from_lexeme(token.text.to_string()))
+        }
+        TokenType::Int => {
+            if let Ok(v) = token.text.parse() {
+                Number::Int(v)
+            } else if let Ok(v) = token.text.parse() {
+                Number::UInt(v)
+            } else {
+                return Err(number_overflow(token));
+            }
+        }
+        // a number too large for a f64 is not read as infinity
+        TokenType::Float => match token.text.parse::<f64>() {
+            Ok(v) if v.is_finite() => Number::Float(v),
+            Ok(_) => return Err(number_overflow(token)),
+            Err(_) => return Err(invalid_number(token)),
+        },
+        TokenType::NonFinite => match token.text.parse() {
+            Ok(v) => Number::Float(v),
+            Err(_) => return Err(invalid_number(token)),
+        },
+        _ => return Err(unexpected_token(token)),
+    })
+}
+
+/// add a member to `object` following `ParseOptions::duplicate_keys`,
+/// `seen` is kept by the caller for the whole object. `span` is where the
+/// key was parsed from, it is kept with the value that is kept
+pub(crate) fn put_member(
+    options: &ParseOptions,
+    object: &mut JsonObject,
+    seen: &mut HashMap<String, Position>,
+    key: String,
+    span: Span,
+    field: Box<dyn JsonElement>,
+) -> Result<()> {
+    let position = span.start;
+    let key_span = options.spans.then_some(span);
+    let put = |object: &mut JsonObject, key: &str, field| {
+        object.put_spanned(key, field, key_span);
+    };
+    match options.duplicate_keys {
+        DuplicateKeys::KeepLast => put(object, &key, field),
+        DuplicateKeys::KeepFirst => {
+            if object.get(&key).is_err() {
+                put(object, &key, field);
+            }
         }
+        DuplicateKeys::Error => match seen.get(&key) {
+            Some(first) => return Err(duplicate_key(&key, *first, position)),
+            None => {
+                put(object, &key, field);
+                seen.insert(key, position);
+            }
+        },
+        // `seen` holds the keys whose values were already collected into
+        // an array, so an array value of the first member is not mistaken
+        // for one
+        DuplicateKeys::Collect => match object.get_mut(&key) {
+            Some(values) if seen.contains_key(&key) => {
+                values.array_mut().unwrap().push_ele(field);
+            }
+            Some(value) => {
+                let first = std::mem::replace(value, JsonArray::new());
+                let values = value.array_mut().unwrap();
+                values.push_ele(first);
+                values.push_ele(field);
+                seen.insert(key, position);
+            }
+            None => put(object, &key, field),
+        },
     }
+    Ok(())
+}
+
+pub(crate) fn duplicate_key(key: &str, first: Position, position: Position) -> JsonError {
+    let message = format!("Duplicate key \"{}\", first defined at {}", key, first);
+    JsonError::new(JsonErrorKind::DuplicateKey, message).at(position)
+}
+
+pub(crate) fn too_deep(token: &Token, max_depth: usize) -> JsonError {
+    JsonError::new(
+        JsonErrorKind::DepthLimit,
+        format!("JSON is nested deeper than {} levels", max_depth),
+    )
+    .at(token.position)
+}
+
+fn number_overflow(token: &Token) -> JsonError {
+    JsonError::new(
+        JsonErrorKind::NumberOverflow,
+        format!("Number {} is out of range", token.text),
+    )
+    .at(token.position)
+}
+
+fn invalid_number(token: &Token) -> JsonError {
+    JsonError::new(
+        JsonErrorKind::Syntax,
+        format!("Invalid number {}", token.text),
+    )
+    .at(token.position)
+}
+
+pub(crate) fn unexpected_token(token: &Token) -> JsonError {
+    let err = if token.token_type == TokenType::Eof {
+        JsonError::new(JsonErrorKind::UnexpectedEof, "Unexpected end of JSON")
+    } else {
+        JsonError::new(
+            JsonErrorKind::Syntax,
+            format!("Unexpected token {}", token.text),
+        )
+    };
+    err.at(token.position)
 }
@@ -0,0 +1,430 @@
+use std::{borrow::Cow, collections::HashMap, io::Read};
+
+use super::{
+    json_parser::{duplicate_key, number_value, too_deep, unexpected_token, Number},
+    DuplicateKeys, JsonError, JsonErrorKind, Lexer, ParseOptions, Position, RawNumber, Result,
+    Source, Token, TokenType,
+};
+
+/// event produced by `JsonPullParser`, keys and strings without escapes
+/// borrow from a string input
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// key of the object member whose value comes next
+    Key(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Int(i64),
+    /// integer above `i64::MAX`
+    UInt(u64),
+    Float(f64),
+    /// number kept as written, see `ParseOptions::raw_numbers`
+    RawNumber(RawNumber),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    /// right after `[`, or after a comma in an array with JSON5
+    ValueOrEnd,
+    /// after a value inside an array or object
+    CommaOrEnd,
+    /// right after `{`, or after a comma in an object with JSON5
+    KeyOrEnd,
+    /// after a comma inside an object
+    Key,
+    /// after a key
+    Colon,
+    /// after the top level value when only one is read
+    Eof,
+}
+
+/// an array or object whose closing bracket was not pushed yet
+struct Open {
+    /// `TokenType::LBracket` or `TokenType::LCurlyBracket`
+    bracket: TokenType,
+    /// elements or members so far
+    members: usize,
+    /// first position of each key, only kept for `DuplicateKeys::Error`
+    seen: HashMap<String, Position>,
+}
+
+/// the JSON grammar as a state machine: tokens are pushed one at a time
+/// and the event each of them completes is returned. it is driven by
+/// `JsonPullParser` and by `IncrementalParser`
+pub(crate) struct Grammar {
+    options: ParseOptions,
+    expect: Expect,
+    stack: Vec<Open>,
+    /// values of the current top level value, see `ParseLimits::max_nodes`
+    nodes: usize,
+    /// top level values follow one another instead of the input ending
+    /// after the first
+    stream: bool,
+}
+
+impl Grammar {
+    pub(crate) fn new(options: ParseOptions, stream: bool) -> Grammar {
+        Grammar {
+            options,
+            expect: Expect::Value,
+            stack: vec![],
+            nodes: 0,
+            stream,
+        }
+    }
+
+    /// the event `token` completes, `None` for punctuation. a `skip`ped
+    /// token has no text, its value is not converted and its key is not
+    /// checked for duplicates
+    pub(crate) fn push<'t>(
+        &mut self,
+        token: Token<'t>,
+        skip: bool,
+    ) -> Result<Option<JsonEvent<'t>>> {
+        let event = match (self.expect, token.token_type) {
+            (Expect::Eof, TokenType::Eof) => return Ok(None),
+            (Expect::ValueOrEnd, TokenType::RBracket)
+            | (Expect::CommaOrEnd, TokenType::RBracket | TokenType::RCurlyBracket)
+            | (Expect::KeyOrEnd, TokenType::RCurlyBracket) => self.end_container(&token)?,
+            (Expect::Value | Expect::ValueOrEnd, _) => return self.start_value(token, skip),
+            (Expect::CommaOrEnd, TokenType::Comma) => {
+                let json5 = self.options.json5;
+                self.expect = match self.stack.last() {
+                    Some(open) if open.bracket == TokenType::LCurlyBracket && json5 => {
+                        Expect::KeyOrEnd
+                    }
+                    Some(open) if open.bracket == TokenType::LCurlyBracket => Expect::Key,
+                    _ if json5 => Expect::ValueOrEnd,
+                    _ => Expect::Value,
+                };
+                return Ok(None);
+            }
+            (Expect::KeyOrEnd | Expect::Key, _)
+                if token.token_type == TokenType::String
+                    || (self.options.json5 && token.is_identifier()) =>
+            {
+                self.key(token, skip)?
+            }
+            (Expect::Colon, TokenType::Colon) => {
+                self.expect = Expect::Value;
+                return Ok(None);
+            }
+            _ => return Err(unexpected_token(&token)),
+        };
+        Ok(Some(event))
+    }
+
+    fn start_value<'t>(&mut self, token: Token<'t>, skip: bool) -> Result<Option<JsonEvent<'t>>> {
+        let container = match token.token_type {
+            TokenType::LBracket | TokenType::LCurlyBracket => true,
+            TokenType::Int
+            | TokenType::Float
+            | TokenType::NonFinite
+            | TokenType::String
+            | TokenType::Bool
+            | TokenType::Null => false,
+            _ => return Err(unexpected_token(&token)),
+        };
+        let limits = &self.options.limits;
+        if self.stack.is_empty() {
+            self.nodes = 0;
+        }
+        if self.nodes == limits.max_nodes {
+            let message = format!("JSON has more than {} values", limits.max_nodes);
+            return Err(JsonError::new(JsonErrorKind::TooManyNodes, message).at(token.position));
+        }
+        if let Some(open) = self.stack.last_mut() {
+            if open.bracket == TokenType::LBracket {
+                let max = limits.max_array_elements;
+                if open.members == max {
+                    let message = format!("Array has more than {} elements", max);
+                    let err = JsonError::new(JsonErrorKind::TooManyElements, message);
+                    return Err(err.at(token.position));
+                }
+                open.members += 1;
+            }
+        }
+        self.nodes += 1;
+        if !container {
+            let event = match skip {
+                true => None,
+                false => Some(primitive_event(token, &self.options)?),
+            };
+            self.after_value();
+            return Ok(event);
+        }
+        if self.stack.len() >= self.options.max_depth {
+            return Err(too_deep(&token, self.options.max_depth));
+        }
+        self.stack.push(Open {
+            bracket: token.token_type,
+            members: 0,
+            seen: HashMap::new(),
+        });
+        Ok(Some(if token.token_type == TokenType::LBracket {
+            self.expect = Expect::ValueOrEnd;
+            JsonEvent::StartArray
+        } else {
+            self.expect = Expect::KeyOrEnd;
+            JsonEvent::StartObject
+        }))
+    }
+
+    fn key<'t>(&mut self, token: Token<'t>, skip: bool) -> Result<JsonEvent<'t>> {
+        let open = self.stack.last_mut().unwrap();
+        let max = self.options.limits.max_object_members;
+        if open.members == max {
+            let message = format!("Object has more than {} members", max);
+            return Err(JsonError::new(JsonErrorKind::TooManyMembers, message).at(token.position));
+        }
+        open.members += 1;
+        if !skip && self.options.duplicate_keys == DuplicateKeys::Error {
+            if let Some(first) = open.seen.get(token.text.as_ref()) {
+                return Err(duplicate_key(&token.text, *first, token.position));
+            }
+            open.seen.insert(token.text.to_string(), token.position);
+        }
+        self.expect = Expect::Colon;
+        Ok(JsonEvent::Key(token.text))
+    }
+
+    fn end_container(&mut self, token: &Token) -> Result<JsonEvent<'static>> {
+        let event = match (self.stack.pop().map(|open| open.bracket), token.token_type) {
+            (Some(TokenType::LBracket), TokenType::RBracket) => JsonEvent::EndArray,
+            (Some(TokenType::LCurlyBracket), TokenType::RCurlyBracket) => JsonEvent::EndObject,
+            _ => return Err(unexpected_token(token)),
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    fn after_value(&mut self) {
+        self.expect = match self.stack.is_empty() {
+            true if self.stream => Expect::Value,
+            true => Expect::Eof,
+            false => Expect::CommaOrEnd,
+        };
+    }
+
+    /// the input may end here
+    pub(crate) fn is_complete(&self) -> bool {
+        match self.expect {
+            Expect::Eof => true,
+            Expect::Value => self.stream && self.stack.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// arrays and objects open
+    fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// a value or the end of an array may come next, after the colon of
+    /// a key that is not pushed yet
+    fn before_value(&self) -> bool {
+        match self.expect {
+            Expect::Value | Expect::ValueOrEnd | Expect::Colon => true,
+            Expect::CommaOrEnd => self.stack.last().unwrap().bracket == TokenType::LBracket,
+            _ => false,
+        }
+    }
+
+    /// the last token pushed ended a value
+    fn after_value_ended(&self) -> bool {
+        matches!(self.expect, Expect::CommaOrEnd | Expect::Eof)
+    }
+}
+
+/// the event of a string, number, bool or null token
+fn primitive_event<'t>(token: Token<'t>, options: &ParseOptions) -> Result<JsonEvent<'t>> {
+    Ok(match token.token_type {
+        TokenType::String => JsonEvent::String(token.text),
+        TokenType::Bool => JsonEvent::Bool(token.text == "true"),
+        TokenType::Null => JsonEvent::Null,
+        _ => match number_value(&token, options)? {
+            Number::Int(v) => JsonEvent::Int(v),
+            Number::UInt(v) => JsonEvent::UInt(v),
+            Number::Float(v) => JsonEvent::Float(v),
+            Number::Raw(v) => JsonEvent::RawNumber(v),
+        },
+    })
+}
+
+///pull parser, yields the document as a sequence of events without
+///building the tree
+///
+///next() to get the next event and its position
+///
+///skip_value() to skip a whole value
+pub struct JsonPullParser<'a> {
+    lexer: Lexer<'a>,
+    grammar: Grammar,
+    peeked: Option<(JsonEvent<'a>, Position)>,
+    done: bool,
+}
+
+impl<'a> JsonPullParser<'a> {
+    /// pull events from a JSON string
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonEvent,JsonPullParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let json = "{\"guild\":{\"members\":[1,2,3]},\"player\":\"Asuna\"}";
+    ///     let mut events = JsonPullParser::new(json);
+    ///     while let Some((event, _)) = events.next().transpose()? {
+    ///         match event {
+    ///             JsonEvent::Key(key) if key == "guild" => events.skip_value()?,
+    ///             JsonEvent::String(player) => println!("{}", player),
+    ///             _ => {}
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(input: &'a str) -> JsonPullParser<'a> {
+        JsonPullParser::with_options(input, ParseOptions::default())
+    }
+
+    /// pull events from a JSON string with `options`
+    ///
+    /// only `DuplicateKeys::Error` changes the events, a repeated key is
+    /// an error. the other policies decide which member a tree keeps, the
+    /// events have every member
+    pub fn with_options(input: &'a str, options: ParseOptions) -> JsonPullParser<'a> {
+        JsonPullParser::from_lexer(Lexer::new(Source::slice(input.as_bytes())), options)
+    }
+
+    /// pull events from JSON read from `reader`
+    pub fn from_reader<R: Read + 'a>(reader: R) -> JsonPullParser<'a> {
+        JsonPullParser::from_reader_with(reader, ParseOptions::default())
+    }
+
+    /// pull events from JSON read from `reader` with `options`
+    pub fn from_reader_with<R: Read + 'a>(reader: R, options: ParseOptions) -> JsonPullParser<'a> {
+        JsonPullParser::from_lexer(Lexer::new(Source::reader(reader)), options)
+    }
+
+    fn from_lexer(mut lexer: Lexer<'a>, options: ParseOptions) -> JsonPullParser<'a> {
+        lexer.set_options(&options);
+        JsonPullParser {
+            lexer,
+            grammar: Grammar::new(options, false),
+            peeked: None,
+            done: false,
+        }
+    }
+
+    /// the next event without consuming it
+    pub fn peek(&mut self) -> Option<Result<&(JsonEvent<'a>, Position)>> {
+        if self.peeked.is_none() {
+            match self.next_event() {
+                Ok(Some(event)) => self.peeked = Some(event),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.peeked.as_ref().map(Ok)
+    }
+
+    /// skip the value the next event starts, with everything nested in it
+    ///
+    /// call it where a value is expected, after a `JsonEvent::Key` or
+    /// between array elements. at the end of an array nothing is skipped.
+    /// the skipped input is only lexed: strings are not decoded, numbers
+    /// are not converted and brackets are only checked to match
+    pub fn skip_value(&mut self) -> Result<()> {
+        let skipped = self.skip_tokens();
+        if skipped.is_err() {
+            self.done = true;
+        }
+        skipped
+    }
+
+    fn skip_tokens(&mut self) -> Result<()> {
+        // the depth the parser is back at once the value is skipped
+        let depth = match self.peeked.take() {
+            Some((JsonEvent::StartArray | JsonEvent::StartObject, _)) => self.grammar.depth() - 1,
+            // a key or the end of a container, there is no value to skip
+            Some(event @ (JsonEvent::Key(_) | JsonEvent::EndArray | JsonEvent::EndObject, _)) => {
+                self.peeked = Some(event);
+                return Ok(());
+            }
+            Some(_) => return Ok(()),
+            None if self.done || !self.grammar.before_value() => return Ok(()),
+            None => self.grammar.depth(),
+        };
+        loop {
+            let token = self.next_skipped_token()?;
+            let position = token.position;
+            let event = self.grammar.push(token, true)?;
+            if self.grammar.depth() < depth {
+                // the end of the array, it is read as the next event
+                self.peeked = event.map(|event| (event, position));
+                return Ok(());
+            }
+            if self.grammar.depth() == depth && self.grammar.after_value_ended() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<(JsonEvent<'a>, Position)>> {
+        if self.done {
+            return Ok(None);
+        }
+        let event = self.read_event();
+        if !matches!(event, Ok(Some(_))) {
+            self.done = true;
+        }
+        event
+    }
+
+    fn read_event(&mut self) -> Result<Option<(JsonEvent<'a>, Position)>> {
+        loop {
+            let token = self.next_token()?;
+            let position = token.position;
+            let eof = token.token_type == TokenType::Eof;
+            if let Some(event) = self.grammar.push(token, false)? {
+                return Ok(Some((event, position)));
+            }
+            if eof {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token<'a>> {
+        let token = self.lexer.next_token();
+        match self.lexer.diagnostic().first() {
+            Some(err) => Err(err.clone()),
+            None => Ok(token),
+        }
+    }
+
+    /// the next token without the text of strings and numbers
+    fn next_skipped_token(&mut self) -> Result<Token<'a>> {
+        self.lexer.set_skip_text(true);
+        let token = self.next_token();
+        self.lexer.set_skip_text(false);
+        token
+    }
+}
+
+impl<'a> Iterator for JsonPullParser<'a> {
+    type Item = Result<(JsonEvent<'a>, Position)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(event) => Some(Ok(event)),
+            None => self.next_event().transpose(),
+        }
+    }
+}
@@ -1,20 +1,40 @@
 pub use crate::JsonElement;
+#[allow(unused_imports)]
 pub use crate::JsonObject;
-use crate::{JsonArray, JsonPrimitive, JsonType};
+use crate::{JsonArray, JsonPrimitive, JsonType, RawNumber};
 
 pub trait Serializable {
     fn serialize(&self) -> Box<dyn JsonElement>;
 }
 
-impl Serializable for i32 {
-    fn serialize(&self) -> Box<dyn JsonElement> {
-        JsonPrimitive::new(JsonType::Int(*self))
-    }
+macro_rules! serialize_signed {
+    ($($t:ty),*) => {$(
+        impl Serializable for $t {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                JsonPrimitive::new(JsonType::Int(*self as i64))
+            }
+        }
+    )*};
+}
+serialize_signed!(i8, i16, i32, i64, isize, u8, u16, u32);
+
+macro_rules! serialize_unsigned {
+    ($($t:ty),*) => {$(
+        impl Serializable for $t {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                JsonPrimitive::new(match i64::try_from(*self) {
+                    Ok(v) => JsonType::Int(v),
+                    Err(_) => JsonType::UInt(*self as u64),
+                })
+            }
+        }
+    )*};
 }
+serialize_unsigned!(u64, usize);
 
-impl Serializable for usize {
+impl Serializable for f32 {
     fn serialize(&self) -> Box<dyn JsonElement> {
-        JsonPrimitive::new(JsonType::Int(*self as i32))
+        JsonPrimitive::new(JsonType::Float(*self as f64))
     }
 }
 
@@ -24,6 +44,12 @@ impl Serializable for f64 {
     }
 }
 
+impl Serializable for RawNumber {
+    fn serialize(&self) -> Box<dyn JsonElement> {
+        JsonPrimitive::new(JsonType::RawNumber(self.clone()))
+    }
+}
+
 impl Serializable for bool {
     fn serialize(&self) -> Box<dyn JsonElement> {
         JsonPrimitive::new(JsonType::Bool(*self))
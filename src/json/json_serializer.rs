@@ -1,63 +1,187 @@
-use std::collections::BTreeMap;
-
-use crate::{JsonArray, JsonElement, JsonObject, JsonType, Serializable};
+use crate::{JsonElement, JsonType, Serializable, SerializeOptions};
 
 pub fn to_json<T: Serializable>(object: T) -> String {
+    to_json_with(object, &SerializeOptions::default())
+}
+
+/// serialize with non-default options
+///
+/// # Examples
+/// ```
+/// use rjson::{to_json_with,SerializeOptions};
+/// let options = SerializeOptions {
+///     json5: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(to_json_with(vec![f64::INFINITY], &options), "[Infinity]");
+/// ```
+pub fn to_json_with<T: Serializable>(object: T, options: &SerializeOptions) -> String {
     let ele = object.serialize();
-    choose_make(&*ele, 0)
+    let mut out = String::new();
+    write_element(&*ele, options, &mut out);
+    out
 }
-fn choose_make(ele: &dyn JsonElement, layer: usize) -> String {
-    match ele.get_type() {
-        JsonType::Object => make_object(ele.object().unwrap(), layer),
-        JsonType::Array => make_array(ele.array().unwrap(), layer),
-        JsonType::String(str) => format!("\"{}\"", str),
-        JsonType::Bool(b) => b.to_string(),
-        JsonType::Float(f) => f.to_string(),
-        JsonType::Int(i) => i.to_string(),
-        JsonType::Null => "null".to_string(),
-    }
+
+/// array or object whose members are still being written
+enum Frame<'e> {
+    Object {
+        members: Vec<(&'e String, &'e dyn JsonElement)>,
+        next: usize,
+        layer: usize,
+    },
+    Array {
+        items: &'e [Box<dyn JsonElement>],
+        next: usize,
+        layer: usize,
+    },
 }
 
-fn make_object(ele: &JsonObject, layer: usize) -> String {
-    let sorted: BTreeMap<&String, &Box<dyn JsonElement>> =
-        ele.get_children().iter().map(|(k, v)| (k, v)).collect();
-
-    let obj: String = sorted
-        .iter()
-        .enumerate()
-        .map(|(i, (key, item))| {
-            if i == 0 {
-                format!(
-                    "  {}\"{}\": {}",
-                    "  ".repeat(layer),
-                    key,
-                    choose_make(&***item, layer + 1)
-                )
-            } else {
-                format!(
-                    ",\n  {}\"{}\": {}",
-                    "  ".repeat(layer),
-                    key,
-                    choose_make(&***item, layer + 1)
-                )
+/// write `ele` with an explicit stack instead of recursion, so deep trees
+/// cannot overflow the call stack
+pub(crate) fn write_element(ele: &dyn JsonElement, options: &SerializeOptions, out: &mut String) {
+    let mut stack = vec![];
+    open_element(ele, 0, options, out, &mut stack);
+    while let Some(frame) = stack.last_mut() {
+        let (item, layer) = match frame {
+            Frame::Object {
+                members,
+                next,
+                layer,
+            } => {
+                if *next == members.len() {
+                    // an empty object stays `{}` like an empty array
+                    if !options.compact && *next > 0 {
+                        out.push('\n');
+                        out.push_str(&"  ".repeat(*layer));
+                    }
+                    out.push('}');
+                    stack.pop();
+                    continue;
+                }
+                let (key, item) = members[*next];
+                if *next > 0 {
+                    out.push(',');
+                }
+                if !options.compact {
+                    out.push('\n');
+                    out.push_str(&"  ".repeat(*layer + 1));
+                }
+                if options.json5 && is_identifier(key) {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&escape_string(key));
+                }
+                out.push_str(if options.compact { ":" } else { ": " });
+                *next += 1;
+                (item, *layer + 1)
             }
-        })
-        .collect();
-    format!("{{\n{}\n{}}}", obj, "  ".repeat(layer))
-}
-
-fn make_array(ele: &JsonArray, layer: usize) -> String {
-    let arr: String = ele
-        .get_children()
-        .iter()
-        .enumerate()
-        .map(|(i, item)| {
-            if i == 0 {
-                choose_make(&**item, layer)
-            } else {
-                format!(",{}", choose_make(&**item, layer))
+            Frame::Array { items, next, layer } => {
+                if *next == items.len() {
+                    out.push(']');
+                    stack.pop();
+                    continue;
+                }
+                if *next > 0 {
+                    out.push(',');
+                }
+                *next += 1;
+                (&*items[*next - 1], *layer)
+            }
+        };
+        open_element(item, layer, options, out, &mut stack);
+    }
+}
+
+/// write a primitive, or the opening of an array or object and push it
+fn open_element<'e>(
+    ele: &'e dyn JsonElement,
+    layer: usize,
+    options: &SerializeOptions,
+    out: &mut String,
+    stack: &mut Vec<Frame<'e>>,
+) {
+    match ele.get_type() {
+        JsonType::Object => {
+            let mut members: Vec<(&String, &dyn JsonElement)> = ele
+                .object()
+                .unwrap()
+                .get_children()
+                .iter()
+                .map(|(k, v, _)| (k, &**v))
+                .collect();
+            if options.sort_keys {
+                members.sort_by_key(|(k, _)| *k);
             }
-        })
-        .collect();
-    format!("[{}]", arr)
+            out.push('{');
+            stack.push(Frame::Object {
+                members,
+                next: 0,
+                layer,
+            });
+        }
+        JsonType::Array => {
+            out.push('[');
+            stack.push(Frame::Array {
+                items: ele.array().unwrap().get_children(),
+                next: 0,
+                layer,
+            });
+        }
+        JsonType::String(str) => out.push_str(&escape_string(&str)),
+        JsonType::Bool(b) => out.push_str(&b.to_string()),
+        JsonType::Float(f) if options.json5 && f.is_nan() => out.push_str("NaN"),
+        JsonType::Float(f) if options.json5 && f.is_infinite() => {
+            out.push_str(if f > 0.0 { "Infinity" } else { "-Infinity" })
+        }
+        JsonType::Float(f) => out.push_str(&make_float(f)),
+        JsonType::Int(i) => out.push_str(&i.to_string()),
+        JsonType::UInt(u) => out.push_str(&u.to_string()),
+        JsonType::RawNumber(n) => out.push_str(n.as_str()),
+        JsonType::Null => out.push_str("null"),
+    }
+}
+
+/// quote a string and escape it as RFC 8259 requires
+fn escape_string(str: &str) -> String {
+    let mut out = String::with_capacity(str.len() + 2);
+    out.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// a key JSON5 allows without quotes, limited to ASCII
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// JSON has no NaN or infinity, and a float without a fraction
+/// would be read back as an int
+fn make_float(f: f64) -> String {
+    if !f.is_finite() {
+        return "null".to_string();
+    }
+    let s = f.to_string();
+    if s.contains(['.', 'e', 'E']) {
+        s
+    } else {
+        format!("{}.0", s)
+    }
 }
@@ -0,0 +1,68 @@
+use std::io::Read;
+
+use super::{json_parser::SpannedValue, JsonParser, Lexer, ParseOptions, Result, Source};
+
+///parses JSON values written back to back, such as `{"a":1}{"b":2} 3`
+///
+///next() to get the next value and the byte range it spans in the input,
+///iteration stops after the first error
+pub struct JsonStreamParser<'a> {
+    parser: JsonParser<'a>,
+    done: bool,
+}
+
+impl<'a> JsonStreamParser<'a> {
+    /// parse the values in a string
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{JsonStreamParser,Result};
+    /// fn main() -> Result<()>{
+    ///     let input = "{\"a\":1}{\"b\":2} 3";
+    ///     let mut values = JsonStreamParser::new(input);
+    ///     let (first, range) = values.next().unwrap()?;
+    ///     assert_eq!(first["a"].int()?, 1);
+    ///     assert_eq!(range, 0..7);
+    ///     assert_eq!(values.count(), 2);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(input: &'a str) -> JsonStreamParser<'a> {
+        JsonStreamParser::with_options(input, &ParseOptions::default())
+    }
+
+    /// parse the values in a string with non-default options
+    pub fn with_options(input: &'a str, options: &ParseOptions) -> JsonStreamParser<'a> {
+        JsonStreamParser::from_lexer(Lexer::new(Source::slice(input.as_bytes())), options)
+    }
+
+    /// parse the values read from `reader`. an object, array or string is
+    /// returned once its last byte is read, a number or keyword needs the
+    /// byte after it or the end of input
+    pub fn from_reader<R: Read>(reader: &'a mut R) -> JsonStreamParser<'a> {
+        let lexer = Lexer::new(Source::reader(reader));
+        JsonStreamParser::from_lexer(lexer, &ParseOptions::default())
+    }
+
+    fn from_lexer(lexer: Lexer<'a>, options: &ParseOptions) -> JsonStreamParser<'a> {
+        JsonStreamParser {
+            parser: JsonParser::new(lexer, options),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for JsonStreamParser<'_> {
+    type Item = Result<SpannedValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.parser.parse_next();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
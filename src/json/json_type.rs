@@ -1,10 +1,68 @@
+use super::{JsonError, JsonErrorKind, RawNumber, Result};
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonType {
     Object,
     Array,
     String(String),
-    Int(i32),
+    /// integer in the range of i64
+    Int(i64),
+    /// integer above `i64::MAX` that fits a u64
+    UInt(u64),
+    /// number with a fraction or an exponent
     Float(f64),
+    /// number as written in the input, see `ParseOptions::raw_numbers`
+    RawNumber(RawNumber),
     Bool(bool),
     Null,
 }
+
+impl JsonType {
+    pub(crate) fn as_float(&self) -> Result<f64> {
+        match *self {
+            JsonType::Float(v) => Ok(v),
+            JsonType::RawNumber(ref v) if !v.is_integer() => v.to_f64(),
+            _ => jerr!(TypeMismatch, "JSON Element is not a float"),
+        }
+    }
+
+    pub(crate) fn as_i32(&self) -> Result<i32> {
+        let v = self.as_i64()?;
+        match i32::try_from(v) {
+            Ok(v) => Ok(v),
+            Err(_) => jerr!(NumberOverflow, format!("{} does not fit a i32", v)),
+        }
+    }
+
+    pub(crate) fn as_i64(&self) -> Result<i64> {
+        match *self {
+            JsonType::RawNumber(ref v) => v.to_i64(),
+            JsonType::Int(v) => Ok(v),
+            JsonType::UInt(v) => jerr!(NumberOverflow, format!("{} does not fit a i64", v)),
+            _ => jerr!(TypeMismatch, "JSON Element is not a int"),
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Result<u64> {
+        match *self {
+            JsonType::RawNumber(ref v) => v.to_u64(),
+            JsonType::Int(v) if v < 0 => {
+                jerr!(NumberOverflow, format!("{} does not fit a u64", v))
+            }
+            JsonType::Int(v) => Ok(v as u64),
+            JsonType::UInt(v) => Ok(v),
+            _ => jerr!(TypeMismatch, "JSON Element is not a int"),
+        }
+    }
+
+    /// any number as a f64, large integers lose precision
+    pub(crate) fn as_f64(&self) -> Result<f64> {
+        match *self {
+            JsonType::Int(v) => Ok(v as f64),
+            JsonType::UInt(v) => Ok(v as f64),
+            JsonType::Float(v) => Ok(v),
+            JsonType::RawNumber(ref v) => v.to_f64(),
+            _ => jerr!(TypeMismatch, "JSON Element is not a number"),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::{borrow::Cow, ops::Index};
+
+use super::{JsonError, JsonErrorKind, JsonType, Result};
+
+///JSON value that borrows its strings from the parsed input
+///
+///strings without escapes are `Cow::Borrowed` slices of the input,
+///see `JsonParser::parse_borrowed`
+///
+///object members keep their document order
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue<'a> {
+    Object(Vec<(Cow<'a, str>, JsonValue<'a>)>),
+    Array(Vec<JsonValue<'a>>),
+    String(Cow<'a, str>),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl<'a> JsonValue<'a> {
+    /// the type of the value, a string is copied into the `JsonType`. use
+    /// `as_str` to look at a string without copying it
+    pub fn get_type(&self) -> JsonType {
+        match self {
+            JsonValue::Object(_) => JsonType::Object,
+            JsonValue::Array(_) => JsonType::Array,
+            JsonValue::String(v) => JsonType::String(v.to_string()),
+            JsonValue::Int(v) => JsonType::Int(*v),
+            JsonValue::UInt(v) => JsonType::UInt(*v),
+            JsonValue::Float(v) => JsonType::Float(*v),
+            JsonValue::Bool(v) => JsonType::Bool(*v),
+            JsonValue::Null => JsonType::Null,
+        }
+    }
+
+    /// get value as a JSON array
+    pub fn array(&self) -> Result<&[JsonValue<'a>]> {
+        match self {
+            JsonValue::Array(v) => Ok(v),
+            _ => jerr!(TypeMismatch, "JSON Element is not an array"),
+        }
+    }
+
+    /// get value as the members of a JSON object
+    pub fn object(&self) -> Result<&[(Cow<'a, str>, JsonValue<'a>)]> {
+        match self {
+            JsonValue::Object(v) => Ok(v),
+            _ => jerr!(TypeMismatch, "JSON Element is not an object"),
+        }
+    }
+
+    /// get the member of a JSON object, the last one wins if the key repeats
+    ///
+    /// members are not indexed, this compares the keys one by one from the
+    /// last. go through `object()` once to read many members of a large
+    /// object
+    pub fn get(&self, key: &str) -> Result<&JsonValue<'a>> {
+        match self.object()?.iter().rev().find(|(k, _)| k == key) {
+            Some((_, v)) => Ok(v),
+            None => jerr!(MissingKey, format!("key \"{}\" is undefined", key)),
+        }
+    }
+
+    pub fn int(&self) -> Result<i32> {
+        self.number().as_i32()
+    }
+
+    pub fn i64(&self) -> Result<i64> {
+        self.number().as_i64()
+    }
+
+    pub fn u64(&self) -> Result<u64> {
+        self.number().as_u64()
+    }
+
+    pub fn f64(&self) -> Result<f64> {
+        self.number().as_f64()
+    }
+
+    pub fn float(&self) -> Result<f64> {
+        match self {
+            JsonValue::Float(v) => Ok(*v),
+            _ => jerr!(TypeMismatch, "JSON Element is not a float"),
+        }
+    }
+
+    pub fn string(&self) -> Result<&str> {
+        match self.as_str() {
+            Some(v) => Ok(v),
+            None => jerr!(TypeMismatch, "JSON Element is not a string"),
+        }
+    }
+
+    /// the string borrowed from the value, `None` for other types
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self) -> Result<bool> {
+        match self {
+            JsonValue::Bool(v) => Ok(*v),
+            _ => jerr!(TypeMismatch, "JSON Element is not a bool"),
+        }
+    }
+
+    /// numbers as their `JsonType`, without copying strings
+    fn number(&self) -> JsonType {
+        match self {
+            JsonValue::Int(v) => JsonType::Int(*v),
+            JsonValue::UInt(v) => JsonType::UInt(*v),
+            JsonValue::Float(v) => JsonType::Float(*v),
+            _ => JsonType::Null,
+        }
+    }
+
+    /// copy every borrowed string so the value no longer borrows the input
+    pub fn into_owned(self) -> JsonValue<'static> {
+        match self {
+            JsonValue::Object(v) => JsonValue::Object(
+                v.into_iter()
+                    .map(|(k, v)| (Cow::Owned(k.into_owned()), v.into_owned()))
+                    .collect(),
+            ),
+            JsonValue::Array(v) => {
+                JsonValue::Array(v.into_iter().map(JsonValue::into_owned).collect())
+            }
+            JsonValue::String(v) => JsonValue::String(Cow::Owned(v.into_owned())),
+            JsonValue::Int(v) => JsonValue::Int(v),
+            JsonValue::UInt(v) => JsonValue::UInt(v),
+            JsonValue::Float(v) => JsonValue::Float(v),
+            JsonValue::Bool(v) => JsonValue::Bool(v),
+            JsonValue::Null => JsonValue::Null,
+        }
+    }
+}
+
+impl<'a> Index<&str> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: &str) -> &Self::Output {
+        match self {
+            JsonValue::Object(_) => self.get(index).unwrap(),
+            _ => panic!("You can't index this! This is not a JSON object!"),
+        }
+    }
+}
+
+impl<'a> Index<usize> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match self {
+            JsonValue::Array(v) => &v[index],
+            _ => panic!("You can't index this! This is not a JSON array!"),
+        }
+    }
+}
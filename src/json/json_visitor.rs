@@ -0,0 +1,54 @@
+use super::{RawNumber, Result};
+
+///callbacks for `JsonParser::parse_with_visitor`, called in document order
+///
+///every callback does nothing by default, return an error from any of them
+///to stop parsing
+pub trait JsonVisitor {
+    fn visit_object_start(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn visit_object_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// key of the object member whose value is visited next
+    fn visit_key(&mut self, _key: &str) -> Result<()> {
+        Ok(())
+    }
+    fn visit_array_start(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn visit_array_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn visit_string(&mut self, _value: &str) -> Result<()> {
+        Ok(())
+    }
+    fn visit_int(&mut self, _value: i64) -> Result<()> {
+        Ok(())
+    }
+    /// integer above `i64::MAX`
+    fn visit_uint(&mut self, _value: u64) -> Result<()> {
+        Ok(())
+    }
+    fn visit_float(&mut self, _value: f64) -> Result<()> {
+        Ok(())
+    }
+    /// number kept as written with `ParseOptions::raw_numbers`, it is
+    /// visited as an int, uint or float by default
+    fn visit_raw_number(&mut self, value: &RawNumber) -> Result<()> {
+        if !value.is_integer() {
+            return self.visit_float(value.to_f64()?);
+        }
+        match value.to_i64() {
+            Ok(v) => self.visit_int(v),
+            Err(_) => self.visit_uint(value.to_u64()?),
+        }
+    }
+    fn visit_bool(&mut self, _value: bool) -> Result<()> {
+        Ok(())
+    }
+    fn visit_null(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
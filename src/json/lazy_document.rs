@@ -0,0 +1,476 @@
+use std::borrow::Cow;
+
+use super::{
+    incremental_parser::advance,
+    json_parser::{primitive_value, unexpected_token},
+    swar, JsonArray, JsonElement, JsonError, JsonErrorKind, JsonObject, JsonParser, JsonPrimitive,
+    JsonType, Lexer, ParseOptions, Position, Result, Source, Token, TokenType,
+};
+
+/// an array or object found by the structural pass
+#[derive(Debug, Clone, Copy)]
+struct Container {
+    /// offset of the opening bracket
+    open: usize,
+    /// offset of the closing bracket
+    close: usize,
+    members: usize,
+}
+
+///JSON document that is parsed on demand
+///
+///parse() makes one pass over the input to match up brackets, nothing
+///else is parsed or allocated. root() to get the top level value,
+///object(), array() and get() on it find a member by skipping over its
+///siblings without parsing them, and parse() builds the tree of a value
+///
+///the text of a value is only checked when it is reached, so errors in
+///arrays and objects that are never entered go unnoticed
+pub struct LazyDocument<'a> {
+    input: &'a str,
+    /// every array and object in the order they open
+    containers: Vec<Container>,
+    root: (usize, usize),
+}
+
+impl<'a> LazyDocument<'a> {
+    /// index a JSON string
+    ///
+    /// # Examples
+    /// ```
+    /// use rjson::{LazyDocument,Result};
+    /// fn main() -> Result<()>{
+    ///     let json = "{\"guild\":{\"members\":[1,2,3]},\"player\":{\"name\":\"Asuna\",\"floor\":74}}";
+    ///     let doc = LazyDocument::parse(json)?;
+    ///     // only the string "Asuna" is parsed, "guild" is skipped in one step
+    ///     assert_eq!(doc.root().get("player")?.get("name")?.string()?, "Asuna");
+    ///     let members = doc.root().get("guild")?.get("members")?.array()?;
+    ///     assert_eq!(members.len(), 3);
+    ///     assert_eq!(members.get(2)?.int()?, 3);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse(input: &'a str) -> Result<LazyDocument<'a>> {
+        let mut doc = LazyDocument {
+            input,
+            containers: vec![],
+            root: (0, 0),
+        };
+        doc.index()?;
+        let root = doc.value_at(0)?;
+        let (next, _) = doc.token_at(root.end)?;
+        if next.token_type != TokenType::Eof {
+            return Err(doc.locate(unexpected_token(&next)));
+        }
+        doc.root = (root.start, root.end);
+        Ok(doc)
+    }
+
+    /// the top level value
+    pub fn root(&self) -> LazyValue<'_> {
+        LazyValue {
+            doc: self,
+            start: self.root.0,
+            end: self.root.1,
+        }
+    }
+
+    /// the structural pass, finds the closing bracket and the number of
+    /// members of every array and object. strings are skipped in bulk
+    fn index(&mut self) -> Result<()> {
+        let input = self.input;
+        let bytes = input.as_bytes();
+        // containers still open, by index, and whether a member is being
+        // read in them, `None` after a comma
+        let mut open: Vec<(usize, Option<bool>)> = vec![];
+        let mut i = 0;
+        loop {
+            i += swar::whitespace_len(&bytes[i..]);
+            let Some(&c) = bytes.get(i) else {
+                break;
+            };
+            if !matches!(c, b']' | b'}' | b',') {
+                // a member is counted once its first byte is seen, so a
+                // trailing comma adds none
+                if let Some((index, in_member)) = open.last_mut() {
+                    if *in_member != Some(true) {
+                        self.containers[*index].members += 1;
+                        *in_member = Some(true);
+                    }
+                }
+            }
+            match c {
+                b'"' => {
+                    i = self.string_end(i)?;
+                    continue;
+                }
+                b'[' | b'{' => {
+                    open.push((self.containers.len(), Some(false)));
+                    self.containers.push(Container {
+                        open: i,
+                        close: 0,
+                        members: 0,
+                    });
+                }
+                b']' | b'}' => {
+                    let Some((index, Some(_))) = open.pop() else {
+                        return Err(self.unexpected_at(i));
+                    };
+                    let opening = bytes[self.containers[index].open];
+                    if !matches!((opening, c), (b'[', b']') | (b'{', b'}')) {
+                        return Err(self.unexpected_at(i));
+                    }
+                    self.containers[index].close = i;
+                }
+                b',' => match open.last_mut() {
+                    Some((_, in_member @ Some(true))) => *in_member = None,
+                    // a comma that follows no member
+                    Some(_) => return Err(self.unexpected_at(i)),
+                    None => {}
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+        if !open.is_empty() {
+            let err = JsonError::new(JsonErrorKind::UnexpectedEof, "Unexpected end of JSON");
+            return Err(self.locate(err.at(Position::new(bytes.len(), 1, 1))));
+        }
+        Ok(())
+    }
+
+    /// offset after the closing quote of the string opening at `start`
+    fn string_end(&self, start: usize) -> Result<usize> {
+        let bytes = self.input.as_bytes();
+        let mut i = start + 1;
+        loop {
+            i += swar::string_len(bytes.get(i..).unwrap_or(&[]), b'"');
+            match bytes.get(i) {
+                Some(b'"') => return Ok(i + 1),
+                Some(b'\\') => i += 2,
+                // a control character, reported once the string is lexed
+                Some(_) => i += 1,
+                None => {
+                    let err = JsonError::new(JsonErrorKind::UnexpectedEof, "Missing quote");
+                    return Err(self.locate(err.at(Position::new(bytes.len(), 1, 1))));
+                }
+            }
+        }
+    }
+
+    /// the container opening at offset `open`
+    fn container(&self, open: usize) -> Container {
+        let i = self
+            .containers
+            .binary_search_by_key(&open, |c| c.open)
+            .unwrap();
+        self.containers[i]
+    }
+
+    /// the token at or after offset `at` and the offset after it. its
+    /// position is only right in the offset until it is `locate()`d
+    fn token_at(&self, at: usize) -> Result<(Token<'a>, usize)> {
+        let bytes = self.input.as_bytes();
+        let mut lexer = Lexer::new(Source::slice(&bytes[at..]));
+        let mut token = lexer.next_token();
+        if let Some(err) = lexer.diagnostic().first() {
+            let offset = at + err.offset().unwrap_or(0);
+            return Err(self.locate(err.clone().at(Position::new(offset, 1, 1))));
+        }
+        token.position = Position::new(at + token.position.offset, 1, 1);
+        Ok((token, at + lexer.offset()))
+    }
+
+    /// the value at or after offset `at`, an array or object is skipped
+    /// with the index
+    fn value_at(&self, at: usize) -> Result<LazyValue<'_>> {
+        let (token, end) = self.token_at(at)?;
+        let start = token.position.offset;
+        let end = match token.token_type {
+            TokenType::LBracket | TokenType::LCurlyBracket => self.container(start).close + 1,
+            TokenType::String
+            | TokenType::Int
+            | TokenType::Float
+            | TokenType::NonFinite
+            | TokenType::Bool
+            | TokenType::Null => end,
+            _ => return Err(self.locate(unexpected_token(&token))),
+        };
+        Ok(LazyValue {
+            doc: self,
+            start,
+            end,
+        })
+    }
+
+    fn unexpected_at(&self, offset: usize) -> JsonError {
+        match self.token_at(offset) {
+            Ok((token, _)) => self.locate(unexpected_token(&token)),
+            Err(err) => err,
+        }
+    }
+
+    /// set the line and column of an error from its offset
+    fn locate(&self, err: JsonError) -> JsonError {
+        match err.offset() {
+            Some(offset) => {
+                let position = advance(Position::default(), &self.input.as_bytes()[..offset]);
+                err.at(position)
+            }
+            None => err,
+        }
+    }
+}
+
+///a value of a `LazyDocument`, nothing in it is parsed until it is read
+#[derive(Clone, Copy)]
+pub struct LazyValue<'a> {
+    doc: &'a LazyDocument<'a>,
+    start: usize,
+    /// offset after the value
+    end: usize,
+}
+
+impl<'a> LazyValue<'a> {
+    /// get value as a JSON array
+    pub fn array(&self) -> Result<LazyArray<'a>> {
+        match self.first_byte() {
+            b'[' => Ok(LazyArray {
+                container: self.doc.container(self.start),
+                doc: self.doc,
+            }),
+            _ => jerr!(TypeMismatch, "JSON Element is not an array"),
+        }
+    }
+
+    /// get value as a JSON object
+    pub fn object(&self) -> Result<LazyObject<'a>> {
+        match self.first_byte() {
+            b'{' => Ok(LazyObject {
+                container: self.doc.container(self.start),
+                doc: self.doc,
+            }),
+            _ => jerr!(TypeMismatch, "JSON Element is not an object"),
+        }
+    }
+
+    /// get the member of a JSON object, the last one wins if the key repeats
+    pub fn get(&self, key: &str) -> Result<LazyValue<'a>> {
+        self.object()?.get(key)
+    }
+
+    pub fn get_type(&self) -> Result<JsonType> {
+        Ok(self.shallow()?.get_type())
+    }
+
+    pub fn int(&self) -> Result<i32> {
+        self.shallow()?.int()
+    }
+
+    pub fn i64(&self) -> Result<i64> {
+        self.shallow()?.i64()
+    }
+
+    pub fn u64(&self) -> Result<u64> {
+        self.shallow()?.u64()
+    }
+
+    pub fn float(&self) -> Result<f64> {
+        self.shallow()?.float()
+    }
+
+    pub fn f64(&self) -> Result<f64> {
+        self.shallow()?.f64()
+    }
+
+    pub fn string(&self) -> Result<String> {
+        self.shallow()?.string()
+    }
+
+    pub fn bool(&self) -> Result<bool> {
+        self.shallow()?.bool()
+    }
+
+    /// the JSON text of the value
+    pub fn as_str(&self) -> &'a str {
+        &self.doc.input[self.start..self.end]
+    }
+
+    /// parse the value with everything nested in it
+    pub fn parse(&self) -> Result<Box<dyn JsonElement>> {
+        JsonParser::parse(self.as_str()).map_err(|err| match err.offset() {
+            Some(offset) => {
+                let err = err.at(Position::new(self.start + offset, 1, 1));
+                self.doc.locate(err)
+            }
+            None => err,
+        })
+    }
+
+    fn first_byte(&self) -> u8 {
+        self.doc.input.as_bytes()[self.start]
+    }
+
+    /// the value of a primitive. an empty array or object stands in for
+    /// a container, so accessors report the same type mismatch as they
+    /// do on a parsed tree
+    fn shallow(&self) -> Result<Box<dyn JsonElement>> {
+        match self.first_byte() {
+            b'[' => Ok(JsonArray::new()),
+            b'{' => Ok(JsonObject::new()),
+            _ => {
+                let (token, _) = self.doc.token_at(self.start)?;
+                match primitive_value(token, &ParseOptions::default()) {
+                    Ok(value) => Ok(JsonPrimitive::new(value)),
+                    Err(err) => Err(self.doc.locate(err)),
+                }
+            }
+        }
+    }
+}
+
+///a JSON array of a `LazyDocument`
+#[derive(Clone, Copy)]
+pub struct LazyArray<'a> {
+    doc: &'a LazyDocument<'a>,
+    container: Container,
+}
+
+impl<'a> LazyArray<'a> {
+    pub fn len(&self) -> usize {
+        self.container.members
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.container.members == 0
+    }
+
+    /// get the element at `index`, the ones before it are skipped
+    pub fn get(&self, index: usize) -> Result<LazyValue<'a>> {
+        match self.iter().nth(index) {
+            Some(value) => value,
+            None => jerr!(
+                IndexOutOfRange,
+                format!("index {} is out of range for length {}", index, self.len())
+            ),
+        }
+    }
+
+    /// the elements in order, stops after the first error
+    pub fn iter(&self) -> impl Iterator<Item = Result<LazyValue<'a>>> {
+        Members::new(self.doc, self.container, false).map(|m| m.map(|(_, v)| v))
+    }
+}
+
+///a JSON object of a `LazyDocument`
+#[derive(Clone, Copy)]
+pub struct LazyObject<'a> {
+    doc: &'a LazyDocument<'a>,
+    container: Container,
+}
+
+impl<'a> LazyObject<'a> {
+    pub fn len(&self) -> usize {
+        self.container.members
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.container.members == 0
+    }
+
+    /// get the member of `key`, the last one wins if the key repeats.
+    /// only the keys are parsed on the way
+    pub fn get(&self, key: &str) -> Result<LazyValue<'a>> {
+        let mut found = None;
+        for member in self.iter() {
+            let (k, v) = member?;
+            if k == key {
+                found = Some(v);
+            }
+        }
+        match found {
+            Some(v) => Ok(v),
+            None => jerr!(MissingKey, format!("key \"{}\" is undefined", key)),
+        }
+    }
+
+    /// the members in document order, stops after the first error
+    pub fn iter(&self) -> impl Iterator<Item = Result<(Cow<'a, str>, LazyValue<'a>)>> {
+        Members::new(self.doc, self.container, true).map(|m| m.map(|(k, v)| (k.unwrap(), v)))
+    }
+}
+
+/// walks the members of an array or object, yielding the key of an
+/// object member and its value without parsing the value
+struct Members<'a> {
+    doc: &'a LazyDocument<'a>,
+    container: Container,
+    object: bool,
+    /// offset of the next member
+    at: usize,
+    /// the last member ended with a comma
+    comma: bool,
+    done: bool,
+}
+
+impl<'a> Members<'a> {
+    fn new(doc: &'a LazyDocument<'a>, container: Container, object: bool) -> Members<'a> {
+        Members {
+            doc,
+            container,
+            object,
+            at: container.open + 1,
+            comma: false,
+            done: false,
+        }
+    }
+
+    fn next_member(&mut self) -> Result<Option<(Option<Cow<'a, str>>, LazyValue<'a>)>> {
+        let doc = self.doc;
+        let (token, mut at) = doc.token_at(self.at)?;
+        if token.position.offset == self.container.close {
+            if self.comma {
+                return Err(doc.locate(unexpected_token(&token)));
+            }
+            return Ok(None);
+        }
+        let key = if self.object {
+            if token.token_type != TokenType::String {
+                return Err(doc.locate(unexpected_token(&token)));
+            }
+            let (colon, end) = doc.token_at(at)?;
+            if colon.token_type != TokenType::Colon {
+                return Err(doc.locate(unexpected_token(&colon)));
+            }
+            at = end;
+            Some(token.text)
+        } else {
+            at = token.position.offset;
+            None
+        };
+        let value = doc.value_at(at)?;
+        let (next, end) = doc.token_at(value.end)?;
+        self.comma = next.token_type == TokenType::Comma;
+        self.at = match next.token_type {
+            TokenType::Comma => end,
+            _ if next.position.offset == self.container.close => next.position.offset,
+            _ => return Err(doc.locate(unexpected_token(&next))),
+        };
+        Ok(Some((key, value)))
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = Result<(Option<Cow<'a, str>>, LazyValue<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let member = self.next_member();
+        if !matches!(member, Ok(Some(_))) {
+            self.done = true;
+        }
+        member.transpose()
+    }
+}
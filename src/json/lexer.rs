@@ -1,201 +1,794 @@
-use std::collections::VecDeque;
+use std::borrow::Cow;
 
-use crate::json::TokenType;
+use crate::json::{
+    raw_number::is_json_number, swar, JsonError, JsonErrorKind, ParseOptions, Position, Source,
+    TokenType,
+};
 
 #[derive(Debug)]
-pub(crate) struct Token {
+pub(crate) struct Token<'a> {
     pub(crate) token_type: TokenType,
-    pub(crate) position: usize,
-    pub(crate) text: String,
+    pub(crate) position: Position,
+    /// position after the token, set by `Lexer::next_token`
+    pub(crate) end: Position,
+    /// borrowed from the input where it can be
+    pub(crate) text: Cow<'a, str>,
 }
-impl Token {
-    pub(crate) fn new(token_type: TokenType, text: &str, position: usize) -> Token {
+impl<'a> Token<'a> {
+    pub(crate) fn new(
+        token_type: TokenType,
+        text: impl Into<Cow<'a, str>>,
+        position: Position,
+    ) -> Token<'a> {
         Token {
             token_type,
             position,
-            text: text.to_string(),
+            end: position,
+            text: text.into(),
+        }
+    }
+
+    /// whether the token can be an unquoted JSON5 key, which includes the
+    /// reserved words lexed as literals. a signed `+Infinity` is not a key
+    pub(crate) fn is_identifier(&self) -> bool {
+        match self.token_type {
+            TokenType::Identifier | TokenType::Bool | TokenType::Null => true,
+            TokenType::NonFinite => {
+                matches!(self.text.as_ref(), "Infinity" | "NaN")
+                    && self.end.offset - self.position.offset == self.text.len()
+            }
+            _ => false,
         }
     }
 }
 
-pub(crate) struct Lexer {
+pub(crate) struct Lexer<'a> {
+    source: Source<'a>,
     position: usize,
-    input: Box<[u8]>,
-    tokens: VecDeque<Token>,
-    diagnostic: Vec<String>,
+    line: usize,
+    column: usize,
+    /// bytes of the number or keyword being lexed, in-memory input
+    /// is sliced instead
+    lexeme: Vec<u8>,
+    diagnostic: Vec<JsonError>,
+    stop_on_error: bool,
+    /// reject everything RFC 8259 forbids, see `ParseOptions::strict`
+    strict: bool,
+    /// accept the JSON5 extensions, see `ParseOptions::json5`
+    json5: bool,
+    /// input past this offset is treated as missing, see `ParseLimits`
+    max_input_bytes: usize,
+    max_string_length: usize,
+    /// string and number tokens are lexed without their text, for input
+    /// that is skipped
+    skip_text: bool,
+    /// the input was cut off at `max_input_bytes`, it is reported once
+    input_too_large: bool,
+    /// offset where the last whitespace run, comment or token began
+    piece: usize,
 }
 
-impl Lexer {
-    pub fn new(input: String) -> Lexer {
+impl<'a> Lexer<'a> {
+    /// a lexer that ends the input at its first error
+    pub fn new(source: Source<'a>) -> Lexer<'a> {
         Lexer {
+            source,
             position: 0,
-            input: input.as_bytes().into(),
-            tokens: VecDeque::new(),
+            line: 1,
+            column: 1,
+            lexeme: vec![],
             diagnostic: vec![],
+            stop_on_error: true,
+            strict: false,
+            json5: false,
+            max_input_bytes: usize::MAX,
+            max_string_length: usize::MAX,
+            skip_text: false,
+            input_too_large: false,
+            piece: 0,
+        }
+    }
+
+    /// a lexer that keeps going after errors, invalid input becomes
+    /// `TokenType::Error` tokens so the parser can recover from it
+    pub fn recovering(source: Source<'a>) -> Lexer<'a> {
+        Lexer {
+            stop_on_error: false,
+            ..Lexer::new(source)
         }
     }
 
-    pub fn lex(&mut self) -> (&mut VecDeque<Token>, &Vec<String>) {
-        while self.position < self.input.len() && self.diagnostic.is_empty() {
-            if self.current().is_ascii_whitespace() {
+    pub fn set_options(&mut self, options: &ParseOptions) {
+        self.strict = options.strict;
+        self.json5 = options.json5;
+        self.max_input_bytes = options.limits.max_input_bytes;
+        self.max_string_length = options.limits.max_string_length;
+    }
+
+    pub fn set_skip_text(&mut self, skip_text: bool) {
+        self.skip_text = skip_text;
+    }
+
+    /// byte offset of the cursor, the end of the last token lexed
+    pub fn offset(&self) -> usize {
+        self.position
+    }
+
+    /// offset where the last whitespace run, comment or token lexed began,
+    /// where input that ends inside it has to be lexed again from
+    pub fn piece_start(&self) -> usize {
+        self.piece
+    }
+
+    pub fn diagnostic(&mut self) -> &mut Vec<JsonError> {
+        &mut self.diagnostic
+    }
+
+    /// lex the next token, `TokenType::Eof` once the input is used up
+    pub fn next_token(&mut self) -> Token<'a> {
+        let mut token = self.lex();
+        token.end = self.here();
+        token
+    }
+
+    fn lex(&mut self) -> Token<'a> {
+        loop {
+            if self.at_end() || (self.stop_on_error && !self.diagnostic.is_empty()) {
+                return Token::new(TokenType::Eof, "End of JSON", self.here());
+            }
+            self.piece = self.position;
+            if self.white_len() > 0 {
                 self.trim_white();
                 continue;
             }
+            if self.json5 {
+                match self.current() {
+                    b'/' if matches!(self.peek_next(), b'/' | b'*') => {
+                        self.skip_comment();
+                        continue;
+                    }
+                    b'+' | b'-' | b'.' | b'0'..=b'9' => return self.parse_json5_numeric(),
+                    b'\'' => return self.parse_string(),
+                    b'_' | b'$' => return self.parse_keyword(),
+                    _ => {}
+                }
+            }
             if self.current() == b'-' || self.current().is_ascii_digit() {
-                self.parse_numeric();
-                continue;
+                return self.parse_numeric();
             }
             if self.current() == b'"' {
-                self.parse_string();
-                continue;
+                return self.parse_string();
             }
             if self.current().is_ascii_alphabetic() {
-                self.parse_keyword();
-                continue;
+                return self.parse_keyword();
             }
-            self.parse_symbol();
+            return self.parse_symbol();
         }
-        self.tokens
-            .push_back(Token::new(TokenType::Eof, "End of JSON", self.position));
-        (&mut self.tokens, &self.diagnostic)
     }
     fn trim_white(&mut self) {
-        while self.current().is_ascii_whitespace() {
+        loop {
+            let run = swar::whitespace_len(self.buffered());
+            if run > 0 {
+                self.skip_run(run);
+                continue;
+            }
+            let len = self.white_len();
+            if len == 0 {
+                break;
+            }
+            for _ in 0..len {
+                self.next();
+            }
+        }
+    }
+
+    /// bytes of the whitespace character at the cursor, 0 if there is none.
+    /// form feed is only whitespace outside strict mode, JSON5 adds
+    /// vertical tab, no-break space, BOM and the line separators
+    fn white_len(&mut self) -> usize {
+        match self.current() {
+            b' ' | b'\t' | b'\n' | b'\r' => 1,
+            0x0c if !self.strict => 1,
+            0x0b if self.json5 => 1,
+            0xc2 if self.json5 && self.peek_next() == 0xa0 => 2,
+            0xe2 if self.json5
+                && self.peek_next() == 0x80
+                && matches!(self.peek(2), Some(0xa8 | 0xa9)) =>
+            {
+                3
+            }
+            0xef if self.json5 && self.peek_next() == 0xbb && self.peek(2) == Some(0xbf) => 3,
+            _ => 0,
+        }
+    }
+
+    /// skip a `//` or `/* */` comment
+    fn skip_comment(&mut self) {
+        let position = self.here();
+        self.next();
+        if self.current() == b'/' {
+            while !self.at_end() && self.current() != b'\n' {
+                self.next();
+            }
+            return;
+        }
+        self.next();
+        loop {
+            if self.at_end() {
+                self.error(
+                    JsonErrorKind::UnexpectedEof,
+                    "Unterminated comment",
+                    position,
+                );
+                return;
+            }
+            if self.current() == b'*' && self.peek_next() == b'/' {
+                self.next();
+                self.next();
+                return;
+            }
             self.next();
         }
     }
 
-    fn parse_numeric(&mut self) {
+    fn parse_numeric(&mut self) -> Token<'a> {
+        let position = self.here();
         let start = self.position;
         let mut float = false;
+        self.lexeme.clear();
         if self.current() == b'-' {
-            self.next();
+            self.consume();
         }
         if !self.current().is_ascii_digit() {
-            self.tokens
-                .push_back(Token::new(TokenType::Int, "", self.position));
-            self.error(format!(
-                "Missing number after minus sign at position {}",
-                self.position
-            ));
-            return;
+            self.error_here("Missing number after minus sign");
+            return Token::new(TokenType::Error, "-", position);
+        }
+        let leading_zero = self.current() == b'0';
+        let digits = self.get_digits();
+        if self.strict && leading_zero && digits > 1 {
+            self.error(JsonErrorKind::Syntax, "Leading zeros in number", position);
+            return Token::new(TokenType::Error, self.take_lexeme(start), position);
         }
-        self.get_digits();
         if self.current() == b'.' {
             float = true;
-            self.next();
-            self.get_digits();
+            self.consume();
+            if self.get_digits() == 0 && self.strict {
+                self.error_here("Missing digits after decimal point");
+                return Token::new(TokenType::Error, self.take_lexeme(start), position);
+            }
         }
         if self.current() == b'e' || self.current() == b'E' {
-            self.next();
+            float = true;
+            self.consume();
             if self.current() == b'-' || self.current() == b'+' {
-                if self.current() == b'-' {
-                    float = true;
+                self.consume();
+            }
+            // not even lenient parsing has a value for `1e`
+            if self.get_digits() == 0 {
+                self.error_here("Missing digits in exponent");
+                return Token::new(TokenType::Error, self.take_lexeme(start), position);
+            }
+        }
+        let num_type = if float {
+            TokenType::Float
+        } else {
+            TokenType::Int
+        };
+        if self.skip_text {
+            return Token::new(num_type, "", position);
+        }
+        Token::new(num_type, self.take_lexeme(start), position)
+    }
+    /// a JSON5 number: may start with `+` or `.`, end with `.`, be
+    /// hexadecimal, `Infinity` or `NaN`. the token text is rewritten as
+    /// a JSON number where it is not one already
+    fn parse_json5_numeric(&mut self) -> Token<'a> {
+        let position = self.here();
+        let start = self.position;
+        self.lexeme.clear();
+        let sign = self.current();
+        if sign == b'+' || sign == b'-' {
+            self.consume();
+        }
+        if self.current().is_ascii_alphabetic() {
+            let token = self.parse_keyword();
+            return match token.token_type {
+                TokenType::NonFinite if sign == b'-' => {
+                    Token::new(TokenType::NonFinite, format!("-{}", token.text), position)
                 }
-                self.next();
+                TokenType::NonFinite => Token::new(TokenType::NonFinite, token.text, position),
+                TokenType::Error => token,
+                _ => {
+                    self.error(
+                        JsonErrorKind::Syntax,
+                        format!("Unexpected word \"{}\" after sign", token.text),
+                        position,
+                    );
+                    Token::new(TokenType::Error, token.text, position)
+                }
+            };
+        }
+        if self.current() == b'0' && matches!(self.peek_next(), b'x' | b'X') {
+            self.next();
+            self.next();
+            let digits_start = self.position;
+            self.lexeme.clear();
+            while self.current().is_ascii_hexdigit() {
+                self.consume();
+            }
+            let digits = self.take_lexeme(digits_start);
+            if digits.is_empty() {
+                self.error_here("Missing hex digits");
+                return Token::new(TokenType::Error, digits, position);
+            }
+            return match u64::from_str_radix(&digits, 16) {
+                Ok(v) if sign == b'-' => Token::new(TokenType::Int, format!("-{}", v), position),
+                Ok(v) => Token::new(TokenType::Int, v.to_string(), position),
+                Err(_) => {
+                    self.error(
+                        JsonErrorKind::NumberOverflow,
+                        format!("Number 0x{} is out of range", digits),
+                        position,
+                    );
+                    Token::new(TokenType::Error, digits, position)
+                }
+            };
+        }
+        let mut float = false;
+        let mut digits = self.get_digits();
+        if self.current() == b'.' {
+            float = true;
+            self.consume();
+            digits += self.get_digits();
+        }
+        if digits == 0 {
+            self.error_here("Missing digits in number");
+            return Token::new(TokenType::Error, self.take_lexeme(start), position);
+        }
+        if self.current() == b'e' || self.current() == b'E' {
+            float = true;
+            self.consume();
+            if self.current() == b'-' || self.current() == b'+' {
+                self.consume();
+            }
+            if self.get_digits() == 0 {
+                self.error_here("Missing digits in exponent");
+                return Token::new(TokenType::Error, self.take_lexeme(start), position);
             }
-            self.get_digits();
         }
-        let num = &self.input[start..self.position];
-        let num = String::from_utf8_lossy(num);
         let num_type = if float {
             TokenType::Float
         } else {
             TokenType::Int
         };
-        self.tokens
-            .push_back(Token::new(num_type, &num, self.position));
+        let text = self.take_lexeme(start);
+        if is_json_number(text.as_bytes()) {
+            Token::new(num_type, text, position)
+        } else {
+            Token::new(num_type, json5_number(&text), position)
+        }
     }
-    fn get_digits(&mut self) {
+
+    /// consume a run of digits, returns how many there were
+    fn get_digits(&mut self) -> usize {
+        let mut count = 0;
         while self.current().is_ascii_digit() {
-            self.next();
+            self.consume();
+            count += 1;
         }
+        count
     }
 
-    fn parse_string(&mut self) {
+    fn parse_string(&mut self) -> Token<'a> {
+        let position = self.here();
+        let quote = self.current();
         self.next();
-        //only contains literal
         let start = self.position;
-        while self.current() != b'"' {
+        // in-memory input is only copied once an escape is found
+        let mut owned = !self.source.is_slice() && !self.skip_text;
+        let mut text: Vec<u8> = Vec::new();
+        while self.current() != quote {
+            // checked first, so input cut off in a long string reports it
+            let len = if owned {
+                text.len()
+            } else {
+                self.position - start
+            };
+            if len > self.max_string_length {
+                return self.skip_long_string(quote, position);
+            }
+            if self.at_end() {
+                self.error_here("Missing quote");
+                break;
+            }
+            let run = self.string_run(quote);
+            if run > 0 {
+                if owned {
+                    text.extend_from_slice(&self.buffered()[..run]);
+                }
+                self.skip_run(run);
+                continue;
+            }
             if self.current() == b'\\' {
+                if !owned && !self.skip_text {
+                    text.extend_from_slice(self.source.slice_from(start).unwrap());
+                    owned = true;
+                }
                 self.next();
-                match self.current() {
-                    b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' | b'u' => self.next(),
+                let escaped = match self.current() {
+                    b'"' => b'"',
+                    b'\\' => b'\\',
+                    b'/' => b'/',
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'\'' if self.json5 => b'\'',
+                    b'v' if self.json5 => 0x0b,
+                    b'0' if self.json5 && !self.peek_next().is_ascii_digit() => 0,
+                    b'x' if self.json5 => {
+                        self.next();
+                        if let Some(c) = self.parse_hex(2).and_then(char::from_u32) {
+                            if owned {
+                                let mut buf = [0; 4];
+                                text.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                            }
+                        }
+                        continue;
+                    }
+                    b'\r' if self.json5 => {
+                        // line continuation, `\r\n` counts as one line break
+                        self.next();
+                        if self.current() == b'\n' {
+                            self.next();
+                        }
+                        continue;
+                    }
+                    b'\n' if self.json5 => {
+                        self.next();
+                        continue;
+                    }
+                    0xe2 if self.json5
+                        && self.peek_next() == 0x80
+                        && matches!(self.peek(2), Some(0xa8 | 0xa9)) =>
+                    {
+                        for _ in 0..3 {
+                            self.next();
+                        }
+                        continue;
+                    }
+                    b'u' => {
+                        self.next();
+                        if let Some(c) = self.parse_unicode_escape() {
+                            if owned {
+                                let mut buf = [0; 4];
+                                text.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                            }
+                        }
+                        continue;
+                    }
+                    // any other character escapes itself, it is copied as is
+                    // on the next iteration
+                    c if self.json5 && !c.is_ascii_digit() => continue,
                     c => {
-                        self.error(format!(
-                            "Unexpected control character '\\{}' at position {}",
-                            c as char, self.position
-                        ));
-                        self.next()
+                        self.error_here(format!("Unexpected control character '\\{}'", c as char));
+                        continue;
                     }
+                };
+                if owned {
+                    text.push(escaped);
+                }
+                self.next();
+            } else if self.strict && self.current() < 0x20 {
+                self.error_here("Unescaped control character in string");
+                self.next();
+            } else if self.strict && self.current() >= 0x80 {
+                let len = match self.utf8_len() {
+                    Some(len) => len,
+                    None => {
+                        self.error_here("Invalid UTF-8 in string");
+                        self.next();
+                        continue;
+                    }
+                };
+                for _ in 0..len {
+                    if owned {
+                        text.push(self.current());
+                    }
+                    self.next();
                 }
-            } else if self.position >= self.input.len() {
-                self.error(format!("Missing quote at position {}", self.position));
-                return;
             } else {
+                if owned {
+                    text.push(self.current());
+                }
                 self.next();
             }
         }
-        self.next();
-        let text = &self.input[start..self.position - 1];
-        let text = String::from_utf8_lossy(text);
-        self.tokens
-            .push_back(Token::new(TokenType::String, &text, self.position));
+        let len = if owned {
+            text.len()
+        } else {
+            self.position - start
+        };
+        if len > self.max_string_length {
+            return self.skip_long_string(quote, position);
+        }
+        let text = match self.source.slice_from(start) {
+            _ if self.skip_text => Cow::Borrowed(""),
+            Some(slice) if !owned => String::from_utf8_lossy(slice),
+            _ => Cow::Owned(match String::from_utf8(text) {
+                Ok(text) => text,
+                Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            }),
+        };
+        if self.current() == quote {
+            self.next();
+        }
+        Token::new(TokenType::String, text, position)
     }
 
-    fn parse_keyword(&mut self) {
-        let start = self.position;
-        while self.current().is_ascii_alphabetic() {
+    /// number of bytes after the cursor, all buffered, that the string
+    /// holds as they are. in strict mode they must be valid UTF-8
+    fn string_run(&self, quote: u8) -> usize {
+        let bytes = self.buffered();
+        let len = swar::string_len(bytes, quote);
+        if !self.strict {
+            return len;
+        }
+        match std::str::from_utf8(&bytes[..len]) {
+            Ok(_) => len,
+            Err(err) => err.valid_up_to(),
+        }
+    }
+
+    /// report a string longer than `ParseLimits::max_string_length` and
+    /// skip the rest of it without keeping it
+    fn skip_long_string(&mut self, quote: u8, position: Position) -> Token<'a> {
+        let message = format!("String is longer than {} bytes", self.max_string_length);
+        self.error(JsonErrorKind::StringTooLong, message, position);
+        while !self.at_end() && self.current() != quote {
+            if self.current() == b'\\' {
+                self.next();
+            }
+            self.next();
+        }
+        if self.current() == quote {
             self.next();
         }
+        Token::new(TokenType::Error, "", position)
+    }
 
-        let id = &self.input[start..self.position];
-        let id = String::from_utf8_lossy(id);
-        if &id == "true" || &id == "false" {
-            self.tokens
-                .push_back(Token::new(TokenType::Bool, &id, self.position));
-            return;
+    /// decode the digits of a `\uXXXX` escape, combining surrogate pairs
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        let start = Position::new(self.position - 2, self.line, self.column - 2);
+        let high = self.parse_hex(4)?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.current() != b'\\' || self.peek_next() != b'u' {
+                    self.error(JsonErrorKind::Syntax, "Lone surrogate", start);
+                    return None;
+                }
+                self.next();
+                self.next();
+                let low = self.parse_hex(4)?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    self.error(JsonErrorKind::Syntax, "Invalid surrogate pair", start);
+                    return None;
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => {
+                self.error(JsonErrorKind::Syntax, "Lone surrogate", start);
+                return None;
+            }
+            _ => high,
+        };
+        char::from_u32(code)
+    }
+
+    /// length of the UTF-8 encoded character at the cursor, `None` if the
+    /// bytes there are not valid UTF-8
+    fn utf8_len(&mut self) -> Option<usize> {
+        let len = match self.current() {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return None,
+        };
+        let mut buf = [0; 4];
+        for (i, b) in buf.iter_mut().enumerate().take(len) {
+            *b = self.peek(i)?;
         }
-        if &id == "null" {
-            self.tokens
-                .push_back(Token::new(TokenType::Null, &id, self.position));
-            return;
+        std::str::from_utf8(&buf[..len]).ok()?;
+        Some(len)
+    }
+
+    /// the value of the `digits` hex digits of an escape
+    fn parse_hex(&mut self, digits: usize) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..digits {
+            let digit = match (self.current() as char).to_digit(16) {
+                Some(d) => d,
+                None => {
+                    self.error_here("Invalid hex digit in escape");
+                    return None;
+                }
+            };
+            value = value * 16 + digit;
+            self.next();
+        }
+        Some(value)
+    }
+
+    fn parse_keyword(&mut self) -> Token<'a> {
+        let position = self.here();
+        let start = self.position;
+        self.lexeme.clear();
+        while self.current().is_ascii_alphabetic()
+            || (self.json5 && matches!(self.current(), b'0'..=b'9' | b'_' | b'$'))
+        {
+            self.consume();
+        }
+
+        let id = self.take_lexeme(start);
+        match id.as_ref() {
+            "true" => return Token::new(TokenType::Bool, "true", position),
+            "false" => return Token::new(TokenType::Bool, "false", position),
+            "null" => return Token::new(TokenType::Null, "null", position),
+            "Infinity" | "NaN" if self.json5 => {
+                return Token::new(TokenType::NonFinite, id, position)
+            }
+            _ if self.json5 => return Token::new(TokenType::Identifier, id, position),
+            _ => {}
         }
-        self.error(format! {"Unexpected word \"{}\" start from position {}",id,start});
+        self.error(
+            JsonErrorKind::Syntax,
+            format!("Unexpected word \"{}\"", id),
+            position,
+        );
+        Token::new(TokenType::Error, id, position)
     }
 
-    fn parse_symbol(&mut self) {
+    fn parse_symbol(&mut self) -> Token<'a> {
+        let position = self.here();
         let token = match self.current() {
-            b'[' => Token::new(TokenType::LBracket, "[", self.position),
-            b']' => Token::new(TokenType::RBracket, "]", self.position),
-            b'{' => Token::new(TokenType::LCurlyBracket, "{", self.position),
-            b'}' => Token::new(TokenType::RCurlyBracket, "}", self.position),
-            b':' => Token::new(TokenType::Colon, ":", self.position),
-            b',' => Token::new(TokenType::Comma, ",", self.position),
+            b'[' => Token::new(TokenType::LBracket, "[", position),
+            b']' => Token::new(TokenType::RBracket, "]", position),
+            b'{' => Token::new(TokenType::LCurlyBracket, "{", position),
+            b'}' => Token::new(TokenType::RCurlyBracket, "}", position),
+            b':' => Token::new(TokenType::Colon, ":", position),
+            b',' => Token::new(TokenType::Comma, ",", position),
             c => {
-                self.error(format!(
-                    "Unexpected symbol '{}' at position {}",
-                    c as char, self.position
-                ));
-                Token::new(TokenType::Error, "", self.position)
+                self.error_here(format!("Unexpected symbol '{}'", c as char));
+                Token::new(TokenType::Error, (c as char).to_string(), position)
             }
         };
-        self.tokens.push_back(token);
         self.next();
+        token
     }
 
     fn next(&mut self) {
+        match self.current() {
+            b'\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            // continuation bytes of a multi-byte character share its column
+            c if c & 0xC0 == 0x80 => {}
+            _ => self.column += 1,
+        }
         self.position += 1;
+        self.source.bump();
+    }
+
+    /// move past the current byte, keeping it in the lexeme
+    fn consume(&mut self) {
+        if !self.source.is_slice() {
+            let c = self.current();
+            self.lexeme.push(c);
+        }
+        self.next();
+    }
+
+    /// the ASCII lexeme that started at offset `start`
+    fn take_lexeme(&mut self, start: usize) -> Cow<'a, str> {
+        match self.source.slice_from(start) {
+            Some(slice) => String::from_utf8_lossy(slice),
+            None => Cow::Owned(String::from_utf8_lossy(&self.lexeme).into_owned()),
+        }
+    }
+
+    /// the bytes after the cursor that can be scanned in bulk without
+    /// reading, up to `ParseLimits::max_input_bytes`
+    fn buffered(&self) -> &[u8] {
+        let bytes = self.source.buffered();
+        let room = self.max_input_bytes.saturating_sub(self.position);
+        &bytes[..bytes.len().min(room)]
+    }
+
+    /// move past `len` buffered bytes at once, counting lines and columns
+    /// as `next()` does
+    fn skip_run(&mut self, len: usize) {
+        let run = &self.source.buffered()[..len];
+        let columns = |bytes: &[u8]| bytes.iter().filter(|&&c| c & 0xC0 != 0x80).count();
+        match run.iter().rposition(|&c| c == b'\n') {
+            Some(last) => {
+                self.line += run.iter().filter(|&&c| c == b'\n').count();
+                self.column = 1 + columns(&run[last + 1..]);
+            }
+            None => self.column += columns(run),
+        }
+        self.position += len;
+        self.source.bump_by(len);
+    }
+
+    fn here(&self) -> Position {
+        Position::new(self.position, self.line, self.column)
+    }
+
+    fn peek_next(&mut self) -> u8 {
+        self.peek(1).unwrap_or(b'\0')
     }
 
     fn current(&mut self) -> u8 {
-        if self.position < self.input.len() {
-            self.input[self.position]
-        } else {
-            b'\0'
+        self.peek(0).unwrap_or(b'\0')
+    }
+
+    /// the byte `ahead` bytes after the cursor, input past
+    /// `ParseLimits::max_input_bytes` is not read
+    fn peek(&mut self, ahead: usize) -> Option<u8> {
+        if self.position + ahead >= self.max_input_bytes {
+            return None;
         }
+        self.source.peek(ahead)
     }
-    fn error(&mut self, diagnostic: String) {
-        self.diagnostic.push(diagnostic);
+
+    fn at_end(&mut self) -> bool {
+        if self.peek(0).is_some() {
+            return false;
+        }
+        if !self.input_too_large && self.source.peek(0).is_some() {
+            self.input_too_large = true;
+            let message = format!("Input is larger than {} bytes", self.max_input_bytes);
+            self.error(JsonErrorKind::InputTooLarge, message, self.here());
+        }
+        if let Some(err) = self.source.take_error() {
+            let message = format!("Failed to read JSON: {}", err);
+            let err = JsonError::new(JsonErrorKind::Io, message).with_source(err);
+            self.diagnostic.push(err.at(self.here()));
+        }
+        true
+    }
+    fn error(&mut self, kind: JsonErrorKind, message: impl Into<String>, position: Position) {
+        self.diagnostic
+            .push(JsonError::new(kind, message).at(position));
+    }
+
+    /// report an error at the current byte, running out of input is an
+    /// unexpected EOF whatever the message says
+    fn error_here(&mut self, message: impl Into<String>) {
+        let kind = if self.at_end() {
+            JsonErrorKind::UnexpectedEof
+        } else {
+            JsonErrorKind::Syntax
+        };
+        self.error(kind, message, self.here());
+    }
+}
+
+/// rewrite a JSON5 number such as `+.5` or `5.` as a JSON number
+fn json5_number(text: &str) -> String {
+    let text = text.strip_prefix('+').unwrap_or(text);
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len() + 2);
+    for (i, &c) in bytes.iter().enumerate() {
+        if c == b'.' && !(i > 0 && bytes[i - 1].is_ascii_digit()) {
+            out.push('0');
+        }
+        out.push(c as char);
+        if c == b'.' && !bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+            out.push('0');
+        }
     }
+    out
 }
@@ -1,22 +1,70 @@
+/// `Err` result with a `JsonError` of the given kind
+macro_rules! jerr {
+    ($kind:ident, $err:expr) => {
+        Err(JsonError::new(JsonErrorKind::$kind, $err.to_string()))
+    };
+}
+
+mod incremental_parser;
+pub use self::incremental_parser::IncrementalParser;
+#[cfg(feature = "tokio")]
+mod json_async;
+#[cfg(feature = "tokio")]
+pub use self::json_async::to_async_writer;
+#[cfg(feature = "tokio")]
+pub use self::json_async::to_async_writer_with;
 mod json_element;
 pub use self::json_element::JsonArray;
 pub use self::json_element::JsonElement;
 pub use self::json_element::JsonObject;
 pub use self::json_element::JsonPrimitive;
+mod json_lines;
+pub use self::json_lines::JsonLinesReader;
+pub use self::json_lines::JsonLinesWriter;
 mod json_parser;
 pub use self::json_parser::JsonParser;
+mod json_pull_parser;
+pub use self::json_pull_parser::JsonEvent;
+pub use self::json_pull_parser::JsonPullParser;
 mod json_error;
 pub use self::json_error::JsonError;
+pub use self::json_error::JsonErrorKind;
+pub use self::json_error::Position;
 pub use self::json_error::Result;
+pub use self::json_error::Span;
 mod json_serialize;
 pub use self::json_serialize::Serializable;
 mod json_serializer;
 pub use json_serializer::to_json;
+pub use json_serializer::to_json_with;
+mod json_stream_parser;
+pub use self::json_stream_parser::JsonStreamParser;
+mod json_value;
+pub use self::json_value::JsonValue;
+mod json_visitor;
+pub use self::json_visitor::JsonVisitor;
+mod lazy_document;
+pub use self::lazy_document::LazyArray;
+pub use self::lazy_document::LazyDocument;
+pub use self::lazy_document::LazyObject;
+pub use self::lazy_document::LazyValue;
+mod parse_options;
+pub use self::parse_options::DuplicateKeys;
+pub use self::parse_options::ParseLimits;
+pub use self::parse_options::ParseOptions;
+pub(crate) mod raw_number;
+pub use self::raw_number::RawNumber;
+mod serialize_options;
+pub use self::serialize_options::SerializeOptions;
 mod json_type;
 pub use self::json_type::JsonType;
 
+mod encoding;
 mod lexer;
 pub(crate) use lexer::Lexer;
 pub(crate) use lexer::Token;
+mod source;
+pub(crate) use source::Source;
+mod swar;
 mod token_type;
 pub(crate) use token_type::TokenType;
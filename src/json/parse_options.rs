@@ -0,0 +1,97 @@
+///options for `JsonParser::parse_with` and `JsonParser::from_reader_with`
+///
+///the default parses like `JsonParser::parse`
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// keep numbers as their original text in `JsonType::RawNumber`
+    /// instead of converting them
+    pub raw_numbers: bool,
+    /// deepest nesting of arrays and objects accepted, deeper input is a
+    /// `JsonErrorKind::DepthLimit` error. the parser recurses once per
+    /// level, so very large limits can overflow the stack again
+    pub max_depth: usize,
+    /// reject everything RFC 8259 forbids that is accepted by default:
+    /// leading zeros, `1.`, control characters and invalid UTF-8
+    /// in strings, and form feed as whitespace
+    pub strict: bool,
+    /// accept JSON5, see `JsonParser::parse_json5`
+    pub json5: bool,
+    /// what to do when an object has the same key more than once
+    pub duplicate_keys: DuplicateKeys,
+    /// bounds on what the parser allocates, none by default
+    pub limits: ParseLimits,
+    /// record where every element and object key was parsed from, see
+    /// `JsonElement::span` and `JsonObject::key_span`
+    pub spans: bool,
+}
+
+/// policy for keys repeated in one object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// fail with a `JsonErrorKind::DuplicateKey` error that gives the
+    /// positions of both keys
+    Error,
+    /// keep the value of the first member with the key
+    KeepFirst,
+    /// keep the value of the last member with the key, the default
+    KeepLast,
+    /// keep every value, a repeated key becomes an array of its values
+    /// in document order
+    Collect,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            raw_numbers: false,
+            max_depth: 128,
+            strict: false,
+            json5: false,
+            duplicate_keys: DuplicateKeys::KeepLast,
+            limits: ParseLimits::default(),
+            spans: false,
+        }
+    }
+}
+
+///limits for parsing untrusted input, each one has its own `JsonErrorKind`
+///
+///the default has no limits
+///
+/// # Examples
+/// ```
+/// use rjson::{JsonErrorKind,JsonParser,ParseLimits,ParseOptions};
+/// let options = ParseOptions {
+///     limits: ParseLimits {
+///         max_array_elements: 2,
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let err = JsonParser::parse_with("[1, 2, 3]", &options).err().unwrap();
+/// assert_eq!(err.kind(), JsonErrorKind::TooManyElements);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// bytes of input read at most, the rest is not looked at
+    pub max_input_bytes: usize,
+    /// bytes of a string or object key after unescaping
+    pub max_string_length: usize,
+    pub max_object_members: usize,
+    pub max_array_elements: usize,
+    /// values in the whole document, arrays and objects included. each
+    /// value of a stream counts on its own
+    pub max_nodes: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_input_bytes: usize::MAX,
+            max_string_length: usize::MAX,
+            max_object_members: usize::MAX,
+            max_array_elements: usize::MAX,
+            max_nodes: usize::MAX,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+use super::{JsonError, JsonErrorKind, Result};
+
+///number kept as the text it was written as, so it survives a round trip
+///without losing precision
+///
+///to_i64(), to_u64() and to_f64() convert it, failing instead of rounding
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawNumber(String);
+
+impl RawNumber {
+    /// wrap the text of a JSON number, fails if it is not one
+    pub fn new(text: impl Into<String>) -> Result<RawNumber> {
+        let text = text.into();
+        if !is_json_number(text.as_bytes()) {
+            return jerr!(Syntax, format!("\"{}\" is not a JSON number", text));
+        }
+        Ok(RawNumber(text))
+    }
+
+    /// text already checked by the lexer
+    pub(crate) fn from_lexeme(text: String) -> RawNumber {
+        RawNumber(text)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// written without a fraction or an exponent
+    pub fn is_integer(&self) -> bool {
+        !self.0.contains(['.', 'e', 'E'])
+    }
+
+    pub fn to_i64(&self) -> Result<i64> {
+        if !self.is_integer() {
+            return jerr!(TypeMismatch, "JSON Element is not a int");
+        }
+        match self.0.parse() {
+            Ok(v) => Ok(v),
+            Err(_) => jerr!(NumberOverflow, format!("{} does not fit a i64", self.0)),
+        }
+    }
+
+    pub fn to_u64(&self) -> Result<u64> {
+        if !self.is_integer() {
+            return jerr!(TypeMismatch, "JSON Element is not a int");
+        }
+        match self.0.parse() {
+            Ok(v) => Ok(v),
+            Err(_) => jerr!(NumberOverflow, format!("{} does not fit a u64", self.0)),
+        }
+    }
+
+    /// the nearest f64, fails only if the number is too large for one
+    pub fn to_f64(&self) -> Result<f64> {
+        match self.0.parse::<f64>() {
+            Ok(v) if v.is_finite() => Ok(v),
+            _ => jerr!(NumberOverflow, format!("{} does not fit a f64", self.0)),
+        }
+    }
+}
+
+impl fmt::Display for RawNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+pub(crate) fn is_json_number(text: &[u8]) -> bool {
+    let digits = |i: usize| text[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut i = usize::from(text.first() == Some(&b'-'));
+    match digits(i) {
+        0 => return false,
+        1 => i += 1,
+        _ if text[i] == b'0' => return false,
+        n => i += n,
+    }
+    if text.get(i) == Some(&b'.') {
+        match digits(i + 1) {
+            0 => return false,
+            n => i += n + 1,
+        }
+    }
+    if matches!(text.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(text.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        match digits(i) {
+            0 => return false,
+            n => i += n,
+        }
+    }
+    i == text.len()
+}
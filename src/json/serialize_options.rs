@@ -0,0 +1,14 @@
+///options for `to_json_with`
+///
+///the default writes like `to_json`
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    /// write JSON5: keys that are identifiers are left unquoted and
+    /// non-finite floats are written as `Infinity`, `-Infinity` and `NaN`
+    /// instead of `null`
+    pub json5: bool,
+    /// write object members sorted by key instead of in insertion order
+    pub sort_keys: bool,
+    /// write everything on one line without spaces
+    pub compact: bool,
+}
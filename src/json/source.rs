@@ -0,0 +1,132 @@
+use std::io::{self, ErrorKind, Read};
+
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// bytes the lexer reads from, either an in-memory slice or a reader
+/// that is pulled from on demand
+pub(crate) enum Source<'a> {
+    Slice {
+        input: &'a [u8],
+        position: usize,
+    },
+    Reader {
+        reader: Box<dyn Read + 'a>,
+        buffer: Box<[u8]>,
+        start: usize,
+        end: usize,
+        /// the reader hit its end or failed, it is not read again
+        done: bool,
+        error: Option<io::Error>,
+    },
+}
+
+impl<'a> Source<'a> {
+    pub fn slice(input: &'a [u8]) -> Source<'a> {
+        Source::Slice { input, position: 0 }
+    }
+
+    pub fn reader(reader: impl Read + 'a) -> Source<'a> {
+        Source::Reader {
+            reader: Box::new(reader),
+            buffer: vec![0; READ_BUFFER_SIZE].into(),
+            start: 0,
+            end: 0,
+            done: false,
+            error: None,
+        }
+    }
+
+    /// the byte `ahead` bytes after the cursor, `None` past the end of input
+    #[inline]
+    pub fn peek(&mut self, ahead: usize) -> Option<u8> {
+        match self {
+            Source::Slice { input, position } => input.get(*position + ahead).copied(),
+            Source::Reader { .. } => self.peek_reader(ahead),
+        }
+    }
+
+    #[inline]
+    pub fn bump(&mut self) {
+        match self {
+            Source::Slice { position, .. } => *position += 1,
+            Source::Reader { start, end, .. } => *start = (*start + 1).min(*end),
+        }
+    }
+
+    /// move the cursor `n` bytes on, they must be `buffered()`
+    #[inline]
+    pub fn bump_by(&mut self, n: usize) {
+        match self {
+            Source::Slice { position, .. } => *position += n,
+            Source::Reader { start, .. } => *start += n,
+        }
+    }
+
+    /// the bytes after the cursor that are in memory, nothing is read
+    pub fn buffered(&self) -> &[u8] {
+        match self {
+            Source::Slice { input, position } => input.get(*position..).unwrap_or(&[]),
+            Source::Reader {
+                buffer, start, end, ..
+            } => &buffer[*start..*end],
+        }
+    }
+
+    pub fn is_slice(&self) -> bool {
+        matches!(self, Source::Slice { .. })
+    }
+
+    /// the input from offset `start` up to the cursor, only in-memory
+    /// input can be borrowed
+    pub fn slice_from(&self, start: usize) -> Option<&'a [u8]> {
+        match self {
+            Source::Slice { input, position } => Some(&input[start..*position]),
+            Source::Reader { .. } => None,
+        }
+    }
+
+    /// the error that ended a reader early, returned only once
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        match self {
+            Source::Slice { .. } => None,
+            Source::Reader { error, .. } => error.take(),
+        }
+    }
+
+    fn peek_reader(&mut self, ahead: usize) -> Option<u8> {
+        if let Source::Reader {
+            reader,
+            buffer,
+            start,
+            end,
+            done,
+            error,
+        } = self
+        {
+            if *start + ahead >= *end && !*done {
+                buffer.copy_within(*start..*end, 0);
+                *end -= *start;
+                *start = 0;
+                while *end <= ahead {
+                    match reader.read(&mut buffer[*end..]) {
+                        Ok(0) => {
+                            *done = true;
+                            break;
+                        }
+                        Ok(n) => *end += n,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => {
+                            *done = true;
+                            *error = Some(e);
+                            break;
+                        }
+                    }
+                }
+            }
+            if *start + ahead < *end {
+                return Some(buffer[*start + ahead]);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,70 @@
+/// bytes scanned at once as one `u128`, with plain integer arithmetic
+/// (SIMD within a register) that runs the same on every target. blocks are
+/// loaded little-endian so the first byte is the lowest
+const BLOCK: usize = 16;
+/// 0x01 in every byte
+const ONES: u128 = u128::MAX / 0xff;
+/// 0x80 in every byte
+const HIGH: u128 = ONES * 0x80;
+/// 0x7f in every byte
+const LOW: u128 = ONES * 0x7f;
+
+/// high bit set in the bytes of `block` that are zero. the sums cannot
+/// carry into the next byte, so unlike the usual `(x - ONES) & !x` trick
+/// no byte is flagged by mistake
+#[inline]
+fn zero(block: u128) -> u128 {
+    !(((block & LOW) + LOW) | block) & HIGH
+}
+
+/// high bit set in the bytes of `block` equal to `b`
+#[inline]
+fn equal(block: u128, b: u8) -> u128 {
+    zero(block ^ (ONES * b as u128))
+}
+
+/// high bit set in the bytes of `block` below `n`, which is at most 0x80
+#[inline]
+fn below(block: u128, n: u8) -> u128 {
+    !(((block & LOW) + ONES * (0x80 - n) as u128) | block) & HIGH
+}
+
+/// length of the run at the start of `bytes` whose blocks have no flagged
+/// byte, the bytes after the last whole block are checked by `scalar`
+#[inline]
+fn run(bytes: &[u8], flag: impl Fn(u128) -> u128, scalar: impl Fn(u8) -> bool) -> usize {
+    let mut len = 0;
+    for chunk in bytes.chunks_exact(BLOCK) {
+        let stop = flag(u128::from_le_bytes(chunk.try_into().unwrap()));
+        if stop != 0 {
+            return len + stop.trailing_zeros() as usize / 8;
+        }
+        len += BLOCK;
+    }
+    len + bytes[len..].iter().take_while(|&&b| scalar(b)).count()
+}
+
+/// number of bytes of JSON whitespace at the start of `bytes`
+pub(crate) fn whitespace_len(bytes: &[u8]) -> usize {
+    run(
+        bytes,
+        |block| {
+            let white = equal(block, b' ')
+                | equal(block, b'\n')
+                | equal(block, b'\r')
+                | equal(block, b'\t');
+            !white & HIGH
+        },
+        |b| matches!(b, b' ' | b'\n' | b'\r' | b'\t'),
+    )
+}
+
+/// number of bytes at the start of `bytes` that a string quoted with
+/// `quote` holds as they are, up to a quote, backslash or control character
+pub(crate) fn string_len(bytes: &[u8], quote: u8) -> usize {
+    run(
+        bytes,
+        |block| equal(block, quote) | equal(block, b'\\') | below(block, 0x20),
+        |b| b != quote && b != b'\\' && b >= 0x20,
+    )
+}
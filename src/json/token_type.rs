@@ -13,4 +13,8 @@ pub(crate) enum TokenType {
     Eof,
     Bool,
     Null,
+    /// unquoted object key, only lexed in JSON5 mode
+    Identifier,
+    /// `Infinity` or `NaN` with an optional sign, only lexed in JSON5 mode
+    NonFinite,
 }
@@ -2,11 +2,19 @@ pub mod json;
 pub use self::json::*;
 pub use rjson_macro::Serializable;
 
+// the derive names the crate as `rjson`, which the tests below use too
 #[cfg(test)]
+extern crate self as rjson;
+
+#[cfg(test)]
+#[allow(clippy::needless_borrow, clippy::bool_assert_comparison)]
 mod tests {
     use crate::{
-        json::{JsonParser, Result},
-        JsonType,
+        json::{JsonParser, Lexer, Result, Source, TokenType},
+        to_json, to_json_with, DuplicateKeys, IncrementalParser, JsonArray, JsonElement, JsonError,
+        JsonErrorKind, JsonEvent, JsonLinesReader, JsonLinesWriter, JsonObject, JsonPullParser,
+        JsonStreamParser, JsonType, JsonValue, JsonVisitor, LazyDocument, ParseLimits,
+        ParseOptions, RawNumber, Serializable, SerializeOptions, Span,
     };
 
     #[test]
@@ -117,4 +125,1367 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn check_string_escape() -> Result<()> {
+        let r = JsonParser::parse("\"a\\nb\\t\\\"c\\\\\\/\"")?.string()?;
+        assert_eq!(r, "a\nb\t\"c\\/");
+        let r = JsonParser::parse("\"caf\\u00e9 \\ud83d\\ude00\"")?.string()?;
+        assert_eq!(r, "caf\u{e9} \u{1f600}");
+        Ok(())
+    }
+
+    #[test]
+    fn check_invalid_escape() {
+        assert!(JsonParser::parse("\"\\ud83d\"").is_err());
+        assert!(JsonParser::parse("\"\\ude00\"").is_err());
+        assert!(JsonParser::parse("\"\\ud83d\\u0041\"").is_err());
+        assert!(JsonParser::parse("\"\\u12g4\"").is_err());
+        assert!(JsonParser::parse("\"\\x\"").is_err());
+    }
+
+    #[test]
+    fn check_serialize_escape() -> Result<()> {
+        struct Named(String);
+        impl Serializable for Named {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                object.put(&self.0, self.0.clone());
+                object
+            }
+        }
+        let name = "O'Brien \"the \\ quote\"\n\u{1}\u{e9}".to_string();
+        let r = JsonParser::parse(&to_json(Named(name.clone())))?;
+        assert_eq!(r[name.as_str()].string()?, name);
+
+        let r = JsonParser::parse(&to_json(vec![1.0, 2.5]))?;
+        assert_eq!(r[0].float()?, 1.0);
+        assert_eq!(r[1].float()?, 2.5);
+        Ok(())
+    }
+
+    #[test]
+    fn check_error_kind() -> Result<()> {
+        let err = JsonParser::parse("{\n  \"a\": tru\n}").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::Syntax);
+        assert_eq!(err.offset(), Some(9));
+        assert_eq!((err.line(), err.column()), (Some(2), Some(8)));
+
+        let err = JsonParser::parse("[1,").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        let err = JsonParser::parse("\"abc").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        let err = JsonParser::parse("[1,]").err().unwrap();
+        assert_eq!((err.kind(), err.offset()), (JsonErrorKind::Syntax, Some(3)));
+        let err = JsonParser::parse("99999999999999999999").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::NumberOverflow);
+
+        let r = JsonParser::parse("{\"a\":[1]}")?;
+        let r = r.object()?;
+        assert_eq!(r.get("b").err().unwrap().kind(), JsonErrorKind::MissingKey);
+        let a = r.get("a")?;
+        assert_eq!(a.int().err().unwrap().kind(), JsonErrorKind::TypeMismatch);
+        let err = a.array()?.get(1).err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::IndexOutOfRange);
+        Ok(())
+    }
+
+    #[test]
+    fn check_all_errors() -> Result<()> {
+        let json = "{\n  \"a\": [1 2, ],\n  \"b\": tru,\n  \"c\" 3,\n  \"d\": \"ok\"\n}";
+        let (r, errors) = JsonParser::parse_all_errors(json);
+        let lines: Vec<_> = errors.iter().map(|e| e.line().unwrap()).collect();
+        assert_eq!(lines, vec![2, 2, 3, 4]);
+        assert_eq!(r["a"][0].int()?, 1);
+        assert_eq!(r["b"].get_type(), JsonType::Null);
+        assert_eq!(r["c"].int()?, 3);
+        assert_eq!(r["d"].string()?, "ok");
+
+        let (r, errors) = JsonParser::parse_all_errors("[[1, 2}, 3");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].kind(), JsonErrorKind::UnexpectedEof);
+        assert_eq!(r[0][1].int()?, 2);
+
+        let (_, errors) = JsonParser::parse_all_errors("[true]");
+        assert!(errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn check_reader() -> Result<()> {
+        struct Chunked<'a>(&'a [u8]);
+        impl std::io::Read for Chunked<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(3).min(self.0.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+        let long = "x".repeat(20000);
+        let json = format!("{{\"a\": [1, 2.5, \"\\u00e9\"], \"long\": \"{}\"}}", long);
+        let r = JsonParser::from_reader(Chunked(json.as_bytes()))?;
+        assert_eq!(r["a"][1].float()?, 2.5);
+        assert_eq!(r["a"][2].string()?, "\u{e9}");
+        assert_eq!(r["long"].string()?, long);
+
+        let err = JsonParser::from_reader(Chunked(b"[1,\n 2,")).err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        assert_eq!(err.line(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn check_reader_error() {
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk on fire"))
+            }
+        }
+        let err = JsonParser::from_reader(std::io::Read::chain(&b"[1, "[..], Failing))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::Io);
+        assert_eq!(err.offset(), Some(4));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn check_pull_parser() -> Result<()> {
+        use std::borrow::Cow;
+        let json = "{\"skip\": {\"a\": [1, {}]}, \"list\": [true, null],\n\"n\": -2}";
+        let mut events = JsonPullParser::new(json);
+        let mut seen = vec![];
+        while let Some((event, position)) = events.next().transpose()? {
+            if event == JsonEvent::Key("skip".into()) {
+                events.skip_value()?;
+                continue;
+            }
+            seen.push((event, position.line));
+        }
+        assert_eq!(
+            seen,
+            vec![
+                (JsonEvent::StartObject, 1),
+                (JsonEvent::Key("list".into()), 1),
+                (JsonEvent::StartArray, 1),
+                (JsonEvent::Bool(true), 1),
+                (JsonEvent::Null, 1),
+                (JsonEvent::EndArray, 1),
+                (JsonEvent::Key("n".into()), 2),
+                (JsonEvent::Int(-2), 2),
+                (JsonEvent::EndObject, 2),
+            ]
+        );
+
+        let mut events = JsonPullParser::new("[1, 2}");
+        assert!(events.by_ref().take(3).all(|e| e.is_ok()));
+        let err = events.next().unwrap().err().unwrap();
+        assert_eq!((err.kind(), err.offset()), (JsonErrorKind::Syntax, Some(5)));
+        assert!(events.next().is_none());
+
+        // skipped values are only lexed, an out of range number is no error
+        let json = "[1, [99999999999999999999, \"\\u00e9\", {\"k\": [{}]}], 2, {}, \"x\"]";
+        let mut events = JsonPullParser::new(json);
+        assert_eq!(events.next().unwrap()?.0, JsonEvent::StartArray);
+        events.skip_value()?;
+        events.skip_value()?;
+        assert_eq!(events.next().unwrap()?.0, JsonEvent::Int(2));
+        events.skip_value()?;
+        events.skip_value()?;
+        events.skip_value()?;
+        assert_eq!(events.next().unwrap()?.0, JsonEvent::EndArray);
+        assert!(events.next().is_none());
+
+        // text without escapes is borrowed from the input
+        let mut events = JsonPullParser::new("{\"a\": \"b\\n\"}").skip(1);
+        assert!(matches!(
+            events.next().unwrap()?.0,
+            JsonEvent::Key(Cow::Borrowed("a"))
+        ));
+        assert!(matches!(
+            events.next().unwrap()?.0,
+            JsonEvent::String(Cow::Owned(_))
+        ));
+
+        let mut events = JsonPullParser::new("{\"a\": [1, {\"b\": 2]], \"c\": 3}");
+        events.next();
+        events.next();
+        let err = events.skip_value().err().unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::Syntax, Some(17))
+        );
+        assert!(events.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn check_pull_parser_options() -> Result<()> {
+        let json5 = ParseOptions {
+            json5: true,
+            ..Default::default()
+        };
+        let events = JsonPullParser::with_options("{a: [Infinity, 1,], 'b': 2,}", json5.clone())
+            .map(|e| e.map(|(event, _)| event))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(events[1], JsonEvent::Key("a".into()));
+        assert_eq!(events[3], JsonEvent::Float(f64::INFINITY));
+        assert_eq!(events.len(), 9);
+        assert!(JsonPullParser::new("[1,]").any(|e| e.is_err()));
+
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_depth: 2,
+            ..Default::default()
+        };
+        let error = |json| {
+            JsonPullParser::with_options(json, options.clone())
+                .find_map(|e| e.err())
+                .map(|e| (e.kind(), e.offset()))
+        };
+        assert_eq!(
+            error("{\"a\": 1, \"a\": 2}"),
+            Some((JsonErrorKind::DuplicateKey, Some(9)))
+        );
+        assert_eq!(error("[[[]]]"), Some((JsonErrorKind::DepthLimit, Some(2))));
+        assert_eq!(error("{\"a\": [], \"b\": {}}"), None);
+
+        let reader = std::io::Cursor::new(b"{// one\nx: 1}".to_vec());
+        let events = JsonPullParser::from_reader_with(reader, json5).collect::<Result<Vec<_>>>()?;
+        assert_eq!(events[2].0, JsonEvent::Int(1));
+        Ok(())
+    }
+
+    #[test]
+    fn check_visitor() -> Result<()> {
+        #[derive(Default)]
+        struct Collect {
+            log: Vec<String>,
+        }
+        impl JsonVisitor for Collect {
+            fn visit_object_start(&mut self) -> Result<()> {
+                self.log.push("{".to_string());
+                Ok(())
+            }
+            fn visit_key(&mut self, key: &str) -> Result<()> {
+                self.log.push(format!("{}:", key));
+                Ok(())
+            }
+            fn visit_int(&mut self, value: i64) -> Result<()> {
+                if value < 0 {
+                    return Err(JsonError::new(JsonErrorKind::Custom, "negative"));
+                }
+                self.log.push(value.to_string());
+                Ok(())
+            }
+            fn visit_string(&mut self, value: &str) -> Result<()> {
+                self.log.push(value.to_string());
+                Ok(())
+            }
+            fn visit_array_end(&mut self) -> Result<()> {
+                self.log.push("]".to_string());
+                Ok(())
+            }
+        }
+        let mut visitor = Collect::default();
+        JsonParser::parse_with_visitor("{\"a\": [1, \"x\"], \"b\": 2.5}", &mut visitor)?;
+        assert_eq!(visitor.log, vec!["{", "a:", "1", "x", "]", "b:"]);
+
+        let mut visitor = Collect::default();
+        let err = JsonParser::parse_with_visitor("[1, -2, 3]", &mut visitor)
+            .err()
+            .unwrap();
+        assert_eq!((err.kind(), err.offset()), (JsonErrorKind::Custom, Some(4)));
+        assert_eq!(visitor.log, vec!["1"]);
+
+        // raw numbers are visited as the number they hold by default
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..Default::default()
+        };
+        let mut visitor = Collect::default();
+        JsonParser::parse_with_visitor_with("[7, 0.5]", &options, &mut visitor)?;
+        assert_eq!(visitor.log, vec!["7", "]"]);
+        Ok(())
+    }
+
+    #[test]
+    fn check_borrowed() -> Result<()> {
+        use std::borrow::Cow;
+        let json = "{\"name\": \"Kirito\", \"tag\": \"a\\tb\", \"hp\": [100, 2.5, true, null]}";
+        let r = JsonParser::parse_borrowed(json)?;
+        assert!(matches!(
+            r["name"],
+            JsonValue::String(Cow::Borrowed("Kirito"))
+        ));
+        assert!(matches!(r["tag"], JsonValue::String(Cow::Owned(_))));
+        assert_eq!(r["tag"].string()?, "a\tb");
+        assert_eq!(r["name"].as_str(), Some("Kirito"));
+        assert_eq!(r["hp"].as_str(), None);
+        assert_eq!(r["hp"][0].int()?, 100);
+        assert_eq!(r.get("hp")?.array()?[1].float()?, 2.5);
+        assert!(r["hp"][2].bool()?);
+        assert_eq!(r["hp"][3], JsonValue::Null);
+        assert_eq!(r.object()?[0].0, "name");
+        assert_eq!(r.get("mp").err().unwrap().kind(), JsonErrorKind::MissingKey);
+
+        let err = JsonParser::parse_borrowed("[1, 2").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        assert!(JsonParser::parse_borrowed("{\"a\" 1}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn check_numbers() -> Result<()> {
+        let r = JsonParser::parse(
+            "[3000000000, -9223372036854775808, 18446744073709551615, 1e5, 2E-2]",
+        )?;
+        assert_eq!(r[0].i64()?, 3000000000);
+        assert_eq!(
+            r[0].int().err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        assert_eq!(r[1].i64()?, i64::MIN);
+        assert_eq!(
+            r[1].u64().err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        assert_eq!(r[2].u64()?, u64::MAX);
+        assert_eq!(
+            r[2].i64().err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        assert_eq!(r[3].float()?, 100000.0);
+        assert_eq!(r[4].f64()?, 0.02);
+        assert_eq!(r[0].f64()?, 3e9);
+
+        let err = JsonParser::parse("1e400").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::NumberOverflow);
+        let err = JsonParser::parse("-18446744073709551616").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::NumberOverflow);
+        for malformed in ["[1e+]", "[1e]", "[-2.5E-]"] {
+            let err = JsonParser::parse(malformed).err().unwrap();
+            assert_eq!(err.kind(), JsonErrorKind::Syntax, "{}", malformed);
+        }
+
+        let r = JsonParser::parse(&to_json(vec![usize::MAX, 7]))?;
+        assert_eq!(r[0].u64()?, usize::MAX as u64);
+        assert_eq!(r[1].int()?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn check_raw_numbers() -> Result<()> {
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..Default::default()
+        };
+        let json = "[12345678901234567890, 0.1000000000000000055, -1e400, 42]";
+        let r = JsonParser::parse_with(json, &options)?;
+        assert_eq!(r[0].raw_number()?.as_str(), "12345678901234567890");
+        assert_eq!(r[0].u64()?, 12345678901234567890);
+        assert_eq!(
+            r[0].i64().err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        assert_eq!(r[1].float()?, 0.1);
+        assert_eq!(
+            r[1].int().err().unwrap().kind(),
+            JsonErrorKind::TypeMismatch
+        );
+        assert_eq!(
+            r[2].f64().err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        assert_eq!(r[3].int()?, 42);
+        assert_eq!(
+            r[1].get_type(),
+            JsonType::RawNumber(RawNumber::new("0.1000000000000000055")?)
+        );
+
+        let numbers = vec![
+            RawNumber::new("12345678901234567890")?,
+            RawNumber::new("-0.10e+5")?,
+        ];
+        let json = to_json(numbers);
+        assert_eq!(json, "[12345678901234567890,-0.10e+5]");
+        let r = JsonParser::parse_with(&json, &options)?;
+        assert_eq!(r[1].raw_number()?.as_str(), "-0.10e+5");
+
+        for bad in ["", "-", "01", "1.", ".5", "1e", "1e+", "+1", "1 "] {
+            assert!(RawNumber::new(bad).is_err(), "{}", bad);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn check_depth_limit() -> Result<()> {
+        let deep = "[".repeat(100000) + &"]".repeat(100000);
+        let err = JsonParser::parse(&deep).err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::DepthLimit);
+        assert_eq!(err.offset(), Some(128));
+        let err = JsonParser::parse_borrowed(&deep).err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::DepthLimit);
+        let (_, errors) = JsonParser::parse_all_errors(&deep);
+        assert_eq!(errors.len(), 1);
+
+        let options = ParseOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(JsonParser::parse_with("[[1]]", &options).is_ok());
+        let err = JsonParser::parse_with("{\"a\":[[1]]}", &options)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::DepthLimit);
+
+        struct Deep(usize);
+        impl Serializable for Deep {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut array: Box<dyn JsonElement> = JsonArray::new();
+                for _ in 1..self.0 {
+                    let mut outer = JsonArray::new();
+                    outer.push_ele(array);
+                    array = outer;
+                }
+                array
+            }
+        }
+        let json = to_json(Deep(100000));
+        assert_eq!(json.len(), 200000);
+        Ok(())
+    }
+
+    // outcome of each `i_` file as (name, accepted by default, accepted in strict mode)
+    const IMPLEMENTATION_DEFINED: &[(&str, bool, bool)] = &[
+        // numbers beyond f64 are rejected, ones that underflow become 0.0
+        ("i_number_double_huge_neg_exp.json", true, true),
+        ("i_number_huge_exp.json", false, false),
+        ("i_number_neg_int_huge_exp.json", false, false),
+        ("i_number_pos_double_huge_exp.json", false, false),
+        ("i_number_real_neg_overflow.json", false, false),
+        ("i_number_real_pos_overflow.json", false, false),
+        ("i_number_real_underflow.json", true, true),
+        // integers beyond i64 are rejected rather than rounded
+        ("i_number_too_big_neg_int.json", false, false),
+        ("i_number_too_big_pos_int.json", false, false),
+        ("i_number_very_big_negative_int.json", false, false),
+        // escaped surrogates must form a pair
+        ("i_object_key_lone_2nd_surrogate.json", false, false),
+        ("i_string_1st_surrogate_but_2nd_missing.json", false, false),
+        (
+            "i_string_1st_valid_surrogate_2nd_invalid.json",
+            false,
+            false,
+        ),
+        (
+            "i_string_incomplete_surrogate_and_escape_valid.json",
+            false,
+            false,
+        ),
+        ("i_string_incomplete_surrogate_pair.json", false, false),
+        (
+            "i_string_incomplete_surrogates_escape_valid.json",
+            false,
+            false,
+        ),
+        ("i_string_invalid_lonely_surrogate.json", false, false),
+        ("i_string_invalid_surrogate.json", false, false),
+        ("i_string_inverted_surrogates_U+1D11E.json", false, false),
+        ("i_string_lone_second_surrogate.json", false, false),
+        // invalid UTF-8 in strings is replaced with U+FFFD unless strict
+        ("i_string_UTF-8_invalid_sequence.json", true, false),
+        ("i_string_UTF8_surrogate_U+D800.json", true, false),
+        ("i_string_invalid_utf-8.json", true, false),
+        ("i_string_iso_latin_1.json", true, false),
+        ("i_string_lone_utf8_continuation_byte.json", true, false),
+        ("i_string_not_in_unicode_range.json", true, false),
+        ("i_string_overlong_sequence_2_bytes.json", true, false),
+        ("i_string_overlong_sequence_6_bytes.json", true, false),
+        ("i_string_overlong_sequence_6_bytes_null.json", true, false),
+        ("i_string_truncated-utf-8.json", true, false),
+        // BOMs and UTF-16 are only understood by parse_bytes, see ENCODED
+        ("i_string_UTF-16LE_with_BOM.json", false, false),
+        ("i_string_utf16BE_no_BOM.json", false, false),
+        ("i_string_utf16LE_no_BOM.json", false, false),
+        ("i_structure_UTF-8_BOM_empty_object.json", false, false),
+        // deeper than the default max_depth of 128
+        ("i_structure_500_nested_arrays.json", false, false),
+    ];
+
+    const ENCODED: &[&str] = &[
+        "i_string_UTF-16LE_with_BOM.json",
+        "i_string_utf16BE_no_BOM.json",
+        "i_string_utf16LE_no_BOM.json",
+        "i_structure_UTF-8_BOM_empty_object.json",
+    ];
+
+    #[test]
+    fn check_test_suite() -> Result<()> {
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_parsing");
+        let mut files: Vec<_> = std::fs::read_dir(dir).unwrap().collect();
+        files.sort_by_key(|f| f.as_ref().unwrap().file_name());
+        for file in files {
+            let path = file.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let input = std::fs::read(&path).unwrap();
+            let result = JsonParser::from_reader_with(&input[..], &strict);
+            if name.starts_with("y_") {
+                assert!(result.is_ok(), "{}: {}", name, result.err().unwrap());
+                assert!(JsonParser::from_reader(&input[..]).is_ok(), "{}", name);
+            } else if name.starts_with("n_") {
+                assert!(result.is_err(), "{} was accepted", name);
+            } else {
+                let (_, default, strict) = IMPLEMENTATION_DEFINED
+                    .iter()
+                    .find(|(file, _, _)| *file == name)
+                    .unwrap_or_else(|| panic!("{} has no documented outcome", name));
+                let lenient = JsonParser::from_reader(&input[..]);
+                assert_eq!(lenient.is_ok(), *default, "{} in default mode", name);
+                assert_eq!(result.is_ok(), *strict, "{} in strict mode", name);
+            }
+        }
+        for name in ENCODED {
+            let input = std::fs::read(format!("{}/{}", dir, name)).unwrap();
+            assert!(
+                JsonParser::parse_bytes_with(&input, &strict).is_ok(),
+                "{}",
+                name
+            );
+        }
+
+        for lenient in ["[012]", "[1.]", "[\"\t\"]", "[\u{c}1]"] {
+            assert!(JsonParser::parse(lenient).is_ok(), "{}", lenient);
+        }
+        let err = JsonParser::from_reader_with(&b"[\"\xe6\x97\xa5\xd1\x88\xfa\"]"[..], &strict)
+            .err()
+            .unwrap();
+        assert_eq!(err.offset(), Some(7));
+        let err = JsonParser::parse_with("[1, 012]", &strict).err().unwrap();
+        assert_eq!(err.offset(), Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn check_json5() -> Result<()> {
+        let config = r#"
+        // hand-edited
+        {
+            unquoted: 'single \'quoted\' "string"',
+            $key_2: "line \
+continuation",
+            /* hex and decimal points */
+            hex: [0xFF, -0x10, .5, 5., +1, -.25e1,],
+            special: [Infinity, -Infinity, NaN],
+            "quoted": {nested: true,},
+            escapes: '\x41\v\0\q',
+        }
+        "#;
+        let r = JsonParser::parse_json5(config)?;
+        assert_eq!(r["unquoted"].string()?, "single 'quoted' \"string\"");
+        assert_eq!(r["$key_2"].string()?, "line continuation");
+        let hex = r["hex"].array()?;
+        assert_eq!(hex.len(), 6);
+        assert_eq!(hex.get(0)?.int()?, 255);
+        assert_eq!(hex.get(1)?.int()?, -16);
+        assert_eq!(hex.get(2)?.float()?, 0.5);
+        assert_eq!(hex.get(3)?.float()?, 5.0);
+        assert_eq!(hex.get(4)?.int()?, 1);
+        assert_eq!(hex.get(5)?.float()?, -2.5);
+        assert_eq!(r["special"][0].float()?, f64::INFINITY);
+        assert_eq!(r["special"][1].float()?, f64::NEG_INFINITY);
+        assert!(r["special"][2].float()?.is_nan());
+        assert!(r["quoted"]["nested"].bool()?);
+        assert_eq!(r["escapes"].string()?, "A\u{b}\0q");
+        let r = JsonParser::parse_json5("['\\u0041', \"\\uD834\\uDD1E\", '\\ud83d']");
+        assert!(r.is_err());
+        let r = JsonParser::parse_json5("['\\u0041', \"\\uD834\\uDD1E\\x41\"]")?;
+        assert_eq!(r[0].string()?, "A");
+        assert_eq!(r[1].string()?, "\u{1d11e}A");
+        let r = JsonParser::parse_json5("{null: 1, true: 2, false: 3, Infinity: 4, NaN: 5}")?;
+        assert_eq!(r["null"].int()?, 1);
+        assert_eq!(r["NaN"].int()?, 5);
+
+        let json = "{\"a\": [1, 2.5, \"x\"], \"b\": null}";
+        let (r5, r) = (JsonParser::parse_json5(json)?, JsonParser::parse(json)?);
+        assert_eq!(r5["a"][1].float()?, r["a"][1].float()?);
+        assert_eq!(r5["a"][2].string()?, r["a"][2].string()?);
+        assert_eq!(r5["b"].get_type(), r["b"].get_type());
+        let options = ParseOptions {
+            raw_numbers: true,
+            json5: true,
+            ..Default::default()
+        };
+        let r = JsonParser::parse_with("[.5, 0x10]", &options)?;
+        assert_eq!(r[0].raw_number()?.as_str(), "0.5");
+        assert_eq!(r[1].raw_number()?.as_str(), "16");
+
+        for bad in [
+            "[,]",
+            "[1,,]",
+            "{a:1,,}",
+            "[1 /* open",
+            "[.]",
+            "[0x]",
+            "[+true]",
+            "[foo]",
+            "{+Infinity: 1}",
+            "{-NaN: 1}",
+        ] {
+            assert!(JsonParser::parse_json5(bad).is_err(), "{}", bad);
+        }
+        for json5_only in [
+            "[1,]",
+            "{a:1}",
+            "['a']",
+            "[0x1]",
+            "[NaN]",
+            "[1] // end",
+            "{null:1}",
+        ] {
+            assert!(JsonParser::parse(json5_only).is_err(), "{}", json5_only);
+        }
+        assert_eq!(
+            JsonParser::parse_json5("[1e400]").err().unwrap().kind(),
+            JsonErrorKind::NumberOverflow
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn check_json5_non_finite() -> Result<()> {
+        let options = ParseOptions {
+            json5: true,
+            ..Default::default()
+        };
+        let mut lexer = Lexer::new(Source::slice(b"[-Infinity, NaN, 1e400]"));
+        lexer.set_options(&options);
+        let types: Vec<_> = std::iter::from_fn(|| Some(lexer.next_token().token_type))
+            .take_while(|t| *t != TokenType::Eof)
+            .filter(|t| *t != TokenType::Comma)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::LBracket,
+                TokenType::NonFinite,
+                TokenType::NonFinite,
+                TokenType::Float,
+                TokenType::RBracket
+            ]
+        );
+
+        let r = JsonParser::parse_json5("[-Infinity, +NaN]")?;
+        assert_eq!(r[0].f64()?, f64::NEG_INFINITY);
+        assert!(r[1].f64()?.is_nan());
+        let err = JsonParser::parse_json5("[1e400]").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::NumberOverflow);
+        Ok(())
+    }
+
+    #[test]
+    fn check_serialize_json5() -> Result<()> {
+        struct Player;
+        impl Serializable for Player {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                object.put("name", String::from("Kirito"));
+                object.put("two words", 1);
+                object.put("level", vec![f64::NAN, f64::NEG_INFINITY]);
+                object
+            }
+        }
+        struct Reserved;
+        impl Serializable for Reserved {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                for (i, key) in ["null", "true", "false", "Infinity", "NaN"]
+                    .iter()
+                    .enumerate()
+                {
+                    object.put(key, i as i32);
+                }
+                object
+            }
+        }
+        let options = SerializeOptions {
+            json5: true,
+            ..Default::default()
+        };
+        let json5 = to_json_with(Player, &options);
+        assert_eq!(
+            json5,
+            "{\n  name: \"Kirito\",\n  \"two words\": 1,\n  level: [NaN,-Infinity]\n}"
+        );
+        let r = JsonParser::parse_json5(&json5)?;
+        assert_eq!(r["name"].string()?, "Kirito");
+        assert!(r["level"][0].float()?.is_nan());
+        assert!(to_json(Player).contains("\"level\": [null,null]"));
+        let r = JsonParser::parse_json5(&to_json_with(Reserved, &options))?;
+        for (i, key) in ["null", "true", "false", "Infinity", "NaN"]
+            .iter()
+            .enumerate()
+        {
+            assert_eq!(r[*key].int()?, i as i32);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn check_duplicate_keys() -> Result<()> {
+        let json = "{\"hp\": 1, \"mp\": [0], \"hp\": 2,\n \"mp\": 3, \"hp\": 4}";
+        let with = |duplicate_keys| ParseOptions {
+            duplicate_keys,
+            ..Default::default()
+        };
+        let r = JsonParser::parse(json)?;
+        assert_eq!(r["hp"].int()?, 4);
+        let r = JsonParser::parse_with(json, &with(DuplicateKeys::KeepFirst))?;
+        assert_eq!(r["hp"].int()?, 1);
+        assert_eq!(r["mp"][0].int()?, 0);
+        let r = JsonParser::parse_with(json, &with(DuplicateKeys::Collect))?;
+        let hp = r["hp"].array()?;
+        assert_eq!(hp.len(), 3);
+        assert_eq!(hp.get(2)?.int()?, 4);
+        let mp = r["mp"].array()?;
+        assert_eq!(mp.len(), 2);
+        assert_eq!(mp.get(0)?.array()?.len(), 1);
+        assert_eq!(mp.get(1)?.int()?, 3);
+
+        let err = JsonParser::parse_with(json, &with(DuplicateKeys::Error))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::DuplicateKey);
+        assert_eq!(err.offset(), Some(21));
+        assert_eq!(
+            err.to_string(),
+            "Duplicate key \"hp\", first defined at line 1 column 2 at line 1 column 22"
+        );
+        assert!(JsonParser::parse_with("{\"a\":{\"a\":1}}", &with(DuplicateKeys::Error)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn check_key_order() -> Result<()> {
+        let r = JsonParser::parse("{\"zeta\": 1, \"alpha\": 2, \"mid\": {\"b\": 3, \"a\": 4}}")?;
+        let keys: Vec<&str> = r.object()?.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["zeta", "alpha", "mid"]);
+        assert_eq!(r.object()?.len(), 3);
+
+        struct Config;
+        impl Serializable for Config {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                object.put("zeta", 1);
+                object.put("alpha", 2);
+                object.put("zeta", 3);
+                object
+            }
+        }
+        assert_eq!(to_json(Config), "{\n  \"zeta\": 3,\n  \"alpha\": 2\n}");
+        let options = SerializeOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_json_with(Config, &options),
+            "{\n  \"alpha\": 2,\n  \"zeta\": 3\n}"
+        );
+
+        struct Empty;
+        impl Serializable for Empty {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                object.put_ele("a", JsonObject::new());
+                let mut b = JsonArray::new();
+                b.push_ele(JsonObject::new());
+                object.put_ele("b", b);
+                object.put("c", Vec::<i32>::new());
+                object
+            }
+        }
+        assert_eq!(
+            to_json(Empty),
+            "{\n  \"a\": {},\n  \"b\": [{}],\n  \"c\": []\n}"
+        );
+
+        #[derive(Serializable)]
+        struct Guild {
+            name: String,
+            floor: i32,
+            alive: bool,
+            #[exclude]
+            _secret: i32,
+            admin: String,
+        }
+        let guild = Guild {
+            name: "Knights of the Blood".to_string(),
+            floor: 75,
+            alive: true,
+            _secret: 0,
+            admin: "Heathcliff".to_string(),
+        };
+        let r = JsonParser::parse(&to_json(guild))?;
+        let keys: Vec<&str> = r.object()?.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["name", "floor", "alive", "admin"]);
+        Ok(())
+    }
+
+    #[test]
+    fn check_json_lines() -> Result<()> {
+        let input: &[u8] = b"{\"id\": 1}\n\n[2, 3]\r\n{\"id\": tru}\n\"last\"";
+        let mut lines = JsonLinesReader::new(input);
+        assert_eq!(lines.next().unwrap()?["id"].int()?, 1);
+        let blank = lines.next().unwrap().err().unwrap();
+        assert_eq!(blank.line(), Some(2));
+        assert_eq!(lines.next().unwrap()?[1].int()?, 3);
+        let err = lines.next().unwrap().err().unwrap();
+        assert_eq!(
+            (err.line(), err.column(), err.offset()),
+            (Some(4), Some(8), Some(26))
+        );
+        assert_eq!(lines.next().unwrap()?.string()?, "last");
+        assert!(lines.next().is_none());
+
+        let values: Vec<_> = JsonLinesReader::new(input).skip_blank(true).collect();
+        assert_eq!(values.len(), 4);
+        let values = JsonLinesReader::new(input)
+            .skip_invalid(true)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(values.len(), 3);
+
+        struct Entry(i32, &'static str);
+        impl Serializable for Entry {
+            fn serialize(&self) -> Box<dyn JsonElement> {
+                let mut object = JsonObject::new();
+                object.put("id", self.0);
+                object.put("msg", self.1.to_string());
+                object.put("tags", vec![String::from("a\nb")]);
+                object
+            }
+        }
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write(Entry(1, "start"))?;
+        writer.write(Entry(2, "stop"))?;
+        writer.flush()?;
+        let out = writer.into_inner();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "{\"id\":1,\"msg\":\"start\",\"tags\":[\"a\\nb\"]}\n{\"id\":2,\"msg\":\"stop\",\"tags\":[\"a\\nb\"]}\n"
+        );
+        let back = JsonLinesReader::new(&out[..]).collect::<Result<Vec<_>>>()?;
+        assert_eq!(back[1]["msg"].string()?, "stop");
+        Ok(())
+    }
+
+    #[test]
+    fn check_stream_parser() -> Result<()> {
+        let input = "{\"a\":1}{\"b\":2} 3\n\"four\"[5]";
+        let values = JsonStreamParser::new(input).collect::<Result<Vec<_>>>()?;
+        let ranges: Vec<_> = values.iter().map(|(_, r)| r.clone()).collect();
+        assert_eq!(ranges, [0..7, 7..14, 15..16, 17..23, 23..26]);
+        assert_eq!(values[1].0["b"].int()?, 2);
+        assert_eq!(&input[values[3].1.clone()], "\"four\"");
+
+        let mut reader = input.as_bytes();
+        let values = JsonStreamParser::from_reader(&mut reader).collect::<Result<Vec<_>>>()?;
+        assert_eq!(values[4].1, 23..26);
+        assert_eq!(values[2].0.int()?, 3);
+
+        let mut values = JsonStreamParser::new("[1] ]  [2]");
+        assert!(values.next().unwrap().is_ok());
+        assert_eq!(values.next().unwrap().err().unwrap().offset(), Some(4));
+        assert!(values.next().is_none());
+        assert_eq!(JsonStreamParser::new("  \n").count(), 0);
+
+        let (r, consumed) = JsonParser::parse_prefix("  [1, 2]{\"next\":true}")?;
+        assert_eq!((r.array()?.len(), consumed), (2, 8));
+        let err = JsonParser::parse_prefix(" ").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        assert!(JsonParser::parse_prefix("[1, 2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn check_parse_bytes() -> Result<()> {
+        let json = "{\"player\": \"Asuna\u{e9}\", \"floor\": [74]}";
+        let mut utf8 = b"\xef\xbb\xbf".to_vec();
+        utf8.extend_from_slice(json.as_bytes());
+        let utf16le: Vec<u8> = json.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let utf16be: Vec<u8> = [0xfeff]
+            .into_iter()
+            .chain(json.encode_utf16())
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        let utf32le: Vec<u8> = json
+            .chars()
+            .flat_map(|c| (c as u32).to_le_bytes())
+            .collect();
+        let utf32be: Vec<u8> = ['\u{feff}']
+            .into_iter()
+            .chain(json.chars())
+            .flat_map(|c| (c as u32).to_be_bytes())
+            .collect();
+        for bytes in [
+            json.as_bytes(),
+            &utf8,
+            &utf16le,
+            &utf16be,
+            &utf32le,
+            &utf32be,
+        ] {
+            let r = JsonParser::parse_bytes(bytes)?;
+            assert_eq!(r["player"].string()?, "Asuna\u{e9}");
+            assert_eq!(r["floor"][0].int()?, 74);
+        }
+        let suite = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/test_parsing/i_string_UTF-16LE_with_BOM.json"
+        );
+        let r = JsonParser::parse_bytes(&std::fs::read(suite).unwrap())?;
+        assert_eq!(r[0].string()?, "\u{e9}");
+
+        let err = JsonParser::parse_bytes(b"[\"ok\",\n \"\xe6\x97\"]")
+            .err()
+            .unwrap();
+        assert_eq!(err.message(), "Invalid UTF-8");
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (Some(9), Some(2), Some(3))
+        );
+        let err = JsonParser::parse_bytes(b"\xef\xbb\xbf[1,]").err().unwrap();
+        assert_eq!(err.offset(), Some(6));
+        let err = JsonParser::parse_bytes(&[b'[', 0, 0x00, 0xd8, b']', 0])
+            .err()
+            .unwrap();
+        assert_eq!((err.message(), err.offset()), ("Invalid UTF-16", Some(2)));
+        let err = JsonParser::parse_bytes(&[b'[', 0, b']']).err().unwrap();
+        assert_eq!(err.message(), "Truncated UTF-16");
+        Ok(())
+    }
+
+    #[test]
+    fn check_parse_limits() -> Result<()> {
+        let with = |limits| ParseOptions {
+            limits,
+            ..Default::default()
+        };
+        let kind = |json: &str, limits| {
+            JsonParser::parse_with(json, &with(limits))
+                .err()
+                .map(|e| e.kind())
+        };
+
+        let input = ParseLimits {
+            max_input_bytes: 8,
+            ..Default::default()
+        };
+        assert!(JsonParser::parse_with("[1, 2]  ", &with(input.clone())).is_ok());
+        assert_eq!(
+            kind("[1, 2, 3]", input.clone()),
+            Some(JsonErrorKind::InputTooLarge)
+        );
+        // the size is checked first, the invalid UTF-8 past the limit is not decoded
+        let err = JsonParser::parse_bytes_with(b"[1, 2]  \xff", &with(input.clone()))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::InputTooLarge);
+        let endless = std::io::repeat(b' ');
+        let err = JsonParser::from_reader_with(endless, &with(input))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::InputTooLarge, Some(8))
+        );
+
+        let string = ParseLimits {
+            max_string_length: 5,
+            ..Default::default()
+        };
+        assert!(
+            JsonParser::parse_with("[\"abcde\", \"\\u0041bcde\"]", &with(string.clone())).is_ok()
+        );
+        assert_eq!(
+            kind("[\"abcdef\"]", string.clone()),
+            Some(JsonErrorKind::StringTooLong)
+        );
+        assert_eq!(
+            kind("{\"abcdef\": 1}", string.clone()),
+            Some(JsonErrorKind::StringTooLong)
+        );
+        let mut reader = "[\"a\\\"bcdefg\", 1]".as_bytes();
+        let err = JsonParser::from_reader_with(&mut reader, &with(string.clone()))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::StringTooLong, Some(1))
+        );
+        // the string never ends, it is reported once it is too long
+        let mut parser = IncrementalParser::with_options(&with(string));
+        let input = format!("[\"\\u0041{}", "b".repeat(100));
+        let err = input
+            .as_bytes()
+            .chunks(1)
+            .find_map(|chunk| parser.feed(chunk).err())
+            .unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::StringTooLong);
+
+        let members = ParseLimits {
+            max_object_members: 2,
+            ..Default::default()
+        };
+        assert!(JsonParser::parse_with(
+            "{\"a\": 1, \"b\": {\"c\": 1, \"d\": 2}}",
+            &with(members.clone())
+        )
+        .is_ok());
+        let err = JsonParser::parse_with("{\"a\": 1, \"b\": 2, \"c\": {}}", &with(members))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::TooManyMembers, Some(17))
+        );
+
+        let elements = ParseLimits {
+            max_array_elements: 2,
+            ..Default::default()
+        };
+        assert!(JsonParser::parse_with("[[1, 2], [3]]", &with(elements.clone())).is_ok());
+        assert_eq!(
+            kind("[[1, 2, [3]], 4]", elements),
+            Some(JsonErrorKind::TooManyElements)
+        );
+
+        let nodes = ParseLimits {
+            max_nodes: 4,
+            ..Default::default()
+        };
+        assert!(JsonParser::parse_with("{\"a\": [1, 2]}", &with(nodes.clone())).is_ok());
+        let err = JsonParser::parse_with("[[1], [2], 3]", &with(nodes))
+            .err()
+            .unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::TooManyNodes, Some(7))
+        );
+
+        let nodes = with(ParseLimits {
+            max_nodes: 3,
+            ..Default::default()
+        });
+        let stream = JsonStreamParser::with_options("[1,2] [3,4] [5,6]", &nodes);
+        assert_eq!(stream.collect::<Result<Vec<_>>>()?.len(), 3);
+        let mut parser = IncrementalParser::with_options(&nodes);
+        assert_eq!(parser.feed(b"[1,2] [3,4] [5,6]")?.len(), 3);
+        let err = parser.feed(b" [7,8,9]").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::TooManyNodes);
+        Ok(())
+    }
+
+    #[test]
+    fn check_incremental() -> Result<()> {
+        let input = "{\"player\": \"Asu\\u00f1a \u{e9}\\ud83d\\ude00\", \"hp\": [-12.5e1, 300, true, null]}\n[1] \"two\" 3";
+        for split in 0..=input.len() {
+            let mut parser = IncrementalParser::new();
+            let mut values = parser.feed(&input.as_bytes()[..split])?;
+            values.append(&mut parser.feed(&input.as_bytes()[split..])?);
+            values.append(&mut parser.finish()?);
+            assert_eq!(values.len(), 4, "split at {}", split);
+            assert_eq!(values[0]["player"].string()?, "Asu\u{f1}a \u{e9}\u{1f600}");
+            assert_eq!(values[0]["hp"][0].float()?, -125.0);
+            assert_eq!(values[0]["hp"][1].int()?, 300);
+            assert_eq!(values[2].string()?, "two");
+            assert_eq!(values[3].int()?, 3);
+        }
+        let mut parser = IncrementalParser::new();
+        let mut values = vec![];
+        for b in input.bytes() {
+            values.append(&mut parser.feed(&[b])?);
+        }
+        assert_eq!(values.len(), 3);
+        assert_eq!(parser.finish()?.len(), 1);
+
+        let mut parser = IncrementalParser::new();
+        assert!(parser.feed(b"[1, 2")?.is_empty());
+        assert_eq!(parser.feed(b"]")?.len(), 1);
+        assert!(parser.feed(b" 42")?.is_empty());
+        assert_eq!(parser.feed(b"\n")?[0].int()?, 42);
+
+        let mut parser = IncrementalParser::new();
+        parser.feed(b"[1,\n  ")?;
+        let err = parser.feed(b"2,]").err().unwrap();
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (Some(8), Some(2), Some(5))
+        );
+        assert_eq!(parser.feed(b"[]").err().unwrap().offset(), Some(8));
+        let mut parser = IncrementalParser::new();
+        parser.feed(b"{\"a\": \"\\u00")?;
+        let err = parser.finish().err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::UnexpectedEof);
+        let mut parser = IncrementalParser::new();
+        parser.feed(b"{\"a\": [1, 2]")?;
+        let err = parser.finish().err().unwrap();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (JsonErrorKind::UnexpectedEof, Some(12))
+        );
+
+        let options = ParseOptions {
+            json5: true,
+            ..Default::default()
+        };
+        let mut parser = IncrementalParser::with_options(&options);
+        assert!(parser.feed(b"// floor: 1")?.is_empty());
+        assert!(parser.feed(b"\n{floor: 0x4")?.is_empty());
+        assert_eq!(parser.feed(b"A,}")?[0]["floor"].int()?, 74);
+        assert!(parser.feed(b"{nul")?.is_empty());
+        assert_eq!(parser.feed(b"l: '\\u0041'}")?[0]["null"].string()?, "A");
+
+        // the tokens of a chunk are lexed in one go, positions run on
+        // across its lines
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"[1, 2]\n[3,\n  {\"a\": [")?.len(), 1);
+        let err = parser.feed(b"]}, ?]").err().unwrap();
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (Some(24), Some(3), Some(14))
+        );
+
+        // a chunk only scans its own bytes, long tokens and comments in
+        // small chunks would take minutes otherwise
+        let text = "Kirito \\\"Black Swordsman\\\" ".repeat(1 << 15);
+        let input = format!("// {}\n/* {} */ [\"{}\"]", text, text, text);
+        let mut parser = IncrementalParser::with_options(&options);
+        let mut values = vec![];
+        for chunk in input.as_bytes().chunks(5) {
+            values.append(&mut parser.feed(chunk)?);
+        }
+        assert_eq!(values[0][0].string()?.len(), text.len() - (2 << 15));
+        assert!(values[0][0].string()?.starts_with("Kirito \"Black"));
+        Ok(())
+    }
+
+    #[test]
+    fn check_bulk_scan() -> Result<()> {
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        // special bytes at every offset of the 16 byte blocks scanned at once
+        for n in 0..40 {
+            let pad = "x".repeat(n);
+            for (text, expected) in [
+                (
+                    format!("{}\\n\u{e9}{}", pad, pad),
+                    format!("{}\n\u{e9}{}", pad, pad),
+                ),
+                (
+                    format!("{}\u{1f600}\\\"", pad),
+                    format!("{}\u{1f600}\"", pad),
+                ),
+                (format!("{}'", pad), format!("{}'", pad)),
+            ] {
+                let json = format!("{}[\"{}\"]", " \n\t\r".repeat(n), text);
+                let r = JsonParser::parse_with(&json, &strict)?;
+                assert_eq!(r[0].string()?, expected);
+                let r = JsonParser::from_reader(json.as_bytes())?;
+                assert_eq!(r[0].string()?, expected);
+            }
+            let r = JsonParser::parse_json5(&format!("['{}\"\\'']", pad))?;
+            assert_eq!(r[0].string()?, format!("{}\"'", pad));
+
+            let json = format!("[\n{}\"{}\u{e9}{}\", x]", " ".repeat(n), pad, pad);
+            let err = JsonParser::parse(&json).err().unwrap();
+            assert_eq!(
+                (err.line(), err.column()),
+                (Some(2), Some(3 * n + 6)),
+                "{}",
+                json
+            );
+            let json = format!("[\"{}\t\"]", pad);
+            assert!(JsonParser::parse_with(&json, &strict).is_err());
+            assert!(JsonParser::parse(&json).is_ok());
+            let json = format!("[\"{}\u{e9}\"]", pad).into_bytes();
+            let mut invalid = json.clone();
+            invalid[n + 3] = b'x';
+            let err = JsonParser::from_reader_with(&invalid[..], &strict)
+                .err()
+                .unwrap();
+            assert_eq!(err.offset(), Some(n + 2));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn check_lazy_document() -> Result<()> {
+        let json = "{\n  \"guild\": {\"name\": \"KoB\", \"members\": [\"Asuna\", {\"floor\": [74, 75]}, \"[}\"]},\n  \"pla\\u0079er\": \"Kirito\",\n  \"hp\": 1.5e3,\n  \"hp\": 42\n}";
+        let doc = LazyDocument::parse(json)?;
+        let root = doc.root();
+        assert_eq!(root.object()?.len(), 4);
+        assert_eq!(root.get("player")?.string()?, "Kirito");
+        assert_eq!(root.get("hp")?.int()?, 42);
+        let members = root.get("guild")?.get("members")?.array()?;
+        assert_eq!(members.len(), 3);
+        assert_eq!(members.get(1)?.get("floor")?.array()?.get(1)?.int()?, 75);
+        assert_eq!(members.get(2)?.string()?, "[}");
+        assert_eq!(members.get(1)?.as_str(), "{\"floor\": [74, 75]}");
+        let keys: Vec<_> = root
+            .object()?
+            .iter()
+            .map(|m| m.map(|(k, _)| k.into_owned()))
+            .collect::<Result<_>>()?;
+        assert_eq!(keys, ["guild", "player", "hp", "hp"]);
+        let guild = root.get("guild")?.parse()?;
+        assert_eq!(guild["members"][0].string()?, "Asuna");
+
+        let err = root.get("guild")?.int().err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::TypeMismatch);
+        let err = members.get(3).err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::IndexOutOfRange);
+        let err = root.get("level").err().unwrap();
+        assert_eq!(err.kind(), JsonErrorKind::MissingKey);
+        assert!(LazyDocument::parse("[[], {}]")?
+            .root()
+            .array()?
+            .get(0)?
+            .array()?
+            .is_empty());
+
+        // errors in arrays and objects are found once they are entered
+        let doc = LazyDocument::parse("{\"a\": 1,\n \"b\": [2, tru], \"c\": {\"d\" 3}}")?;
+        assert_eq!(doc.root().get("a")?.int()?, 1);
+        let err = doc.root().get("b")?.array()?.get(1).err().unwrap();
+        assert_eq!(
+            (err.kind(), err.line(), err.column()),
+            (JsonErrorKind::Syntax, Some(2), Some(11))
+        );
+        let err = doc.root().get("c")?.get("d").err().unwrap();
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (Some(35), Some(2), Some(27))
+        );
+        let err = doc.root().get("c")?.parse().err().unwrap();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(27)));
+        for (json, kind) in [
+            ("[1, 2", JsonErrorKind::UnexpectedEof),
+            ("[1, 2}", JsonErrorKind::Syntax),
+            ("[\"a]", JsonErrorKind::UnexpectedEof),
+            ("[1] 2", JsonErrorKind::Syntax),
+            ("  ", JsonErrorKind::UnexpectedEof),
+            ("[1, , 2]", JsonErrorKind::Syntax),
+            ("{,}", JsonErrorKind::Syntax),
+        ] {
+            let err = LazyDocument::parse(json).err().unwrap();
+            assert_eq!(err.kind(), kind, "{}", json);
+        }
+        // a trailing comma is no member
+        let err = LazyDocument::parse("[1,2,]").err().unwrap();
+        assert_eq!((err.kind(), err.offset()), (JsonErrorKind::Syntax, Some(5)));
+        assert_eq!(LazyDocument::parse("[1,2 ]")?.root().array()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn check_spans() -> Result<()> {
+        let json =
+            "{\n  \"player\": \"Asu\u{f1}a\",\n  \"floors\": [74, 75.5],\n  \"floors\": {}\n}";
+        let options = ParseOptions {
+            spans: true,
+            ..Default::default()
+        };
+        let text = |span: Option<Span>| {
+            let span = span.unwrap();
+            &json[span.start.offset..span.end.offset]
+        };
+        let r = JsonParser::parse_with(json, &options)?;
+        assert_eq!(text(r.span()), json);
+        let object = r.object()?;
+        assert_eq!(text(object.key_span("player")), "\"player\"");
+        assert_eq!(text(r["player"].span()), "\"Asu\u{f1}a\"");
+        assert_eq!(text(r["floors"].span()), "{}");
+        assert_eq!(text(object.key_span("floors")), "\"floors\"");
+        assert_eq!(object.key_span("floors").unwrap().start.line, 4);
+        let span = r["player"].span().unwrap();
+        assert_eq!(
+            (span.start.line, span.start.column, span.end.column),
+            (2, 13, 20)
+        );
+        assert_eq!(span.to_string(), "line 2 column 13 to line 2 column 20");
+        assert_eq!(object.key_span("level"), None);
+
+        let first = ParseOptions {
+            duplicate_keys: DuplicateKeys::KeepFirst,
+            ..options.clone()
+        };
+        let r = JsonParser::parse_with(json, &first)?;
+        assert_eq!(text(r["floors"][1].span()), "75.5");
+        assert_eq!(r.object()?.key_span("floors").unwrap().start.line, 3);
+
+        for split in [1, 20, 40] {
+            let mut parser = IncrementalParser::with_options(&options);
+            let mut values = parser.feed(&json.as_bytes()[..split])?;
+            values.append(&mut parser.feed(&json.as_bytes()[split..])?);
+            assert_eq!(values[0]["player"].span(), Some(span));
+            assert_eq!(text(values[0]["floors"].span()), "{}");
+        }
+
+        let r = JsonParser::parse(json)?;
+        assert_eq!(r.span(), None);
+        assert_eq!(r["player"].span(), None);
+        assert_eq!(r.object()?.key_span("player"), None);
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn check_async() -> Result<()> {
+        use crate::to_async_writer;
+        use tokio::io::AsyncWriteExt;
+
+        let json = "{\"player\": \"Asu\\u00f1a\", \"hp\": [12345, -6.5], \"login\": true}";
+        let ele = JsonParser::parse(json)?;
+        // a buffer this small splits strings, numbers and escapes
+        let (client, server) = tokio::io::duplex(3);
+        let send = async move { to_async_writer(client, &*ele).await };
+        let (sent, r) = tokio::join!(send, JsonParser::from_async_reader(server));
+        sent?;
+        let r = r?;
+        assert_eq!(r["player"].string()?, "Asu\u{f1}a");
+        assert_eq!(r["hp"][1].float()?, -6.5);
+
+        let (mut client, server) = tokio::io::duplex(4);
+        let send = async move { client.write_all(b"[1, 2]\n  3").await };
+        let (_, r) = tokio::join!(send, JsonParser::from_async_reader(server));
+        let err = r.err().unwrap();
+        assert_eq!(
+            (err.kind(), err.line(), err.column()),
+            (JsonErrorKind::Syntax, Some(2), Some(3))
+        );
+        let (mut client, server) = tokio::io::duplex(4);
+        let send = async move { client.write_all(b"[1, 2").await };
+        let (_, r) = tokio::join!(send, JsonParser::from_async_reader(server));
+        assert_eq!(r.err().unwrap().kind(), JsonErrorKind::UnexpectedEof);
+        let r = JsonParser::from_async_reader(&b" \n"[..]).await;
+        assert_eq!(r.err().unwrap().kind(), JsonErrorKind::UnexpectedEof);
+
+        // a long string arriving a few bytes at a time is scanned once
+        let text = "Link Start! ".repeat(1 << 17);
+        let json = format!("[\"{}\"]", text);
+        let (mut client, server) = tokio::io::duplex(7);
+        let send = async move { client.write_all(json.as_bytes()).await };
+        let (sent, r) = tokio::join!(send, JsonParser::from_async_reader(server));
+        sent.unwrap();
+        assert_eq!(r?[0].string()?, text);
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 extern crate rjson;
 use rjson::{to_json, Result, Serializable};
 #[derive(Serializable)]
+#[allow(clippy::upper_case_acronyms)]
 struct SAO {
     admin: String,
     player: Vec<Player>,